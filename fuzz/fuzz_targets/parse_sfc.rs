@@ -0,0 +1,68 @@
+#![no_main]
+
+use std::ops::Range;
+
+use dprint_plugin_vue::{parse_sfc, validate, visit_blocks, Section, SfcDescriptor};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(source) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    if let Ok(descriptor) = parse_sfc(source) {
+        check_sections(source, &descriptor.sections);
+    }
+
+    let (descriptor, diagnostics) = SfcDescriptor::parse_lenient(source);
+
+    check_sections(source, &descriptor.sections);
+
+    for diagnostic in diagnostics.iter().chain(&validate(source)) {
+        assert!(source.get(diagnostic.range.clone()).is_some());
+    }
+
+    let mut visited = 0;
+
+    if visit_blocks(source, |_| visited += 1).is_ok() {
+        assert_eq!(visited, descriptor.blocks().count());
+    }
+});
+
+/// Check that the sections cover `source` in order and that every slice and span of the blocks
+/// lies within the block.
+fn check_sections(source: &str, sections: &[Section]) {
+    let mut offset = 0;
+
+    for section in sections {
+        match section {
+            Section::Raw(text) => {
+                assert_eq!(text.as_ptr(), source[offset..].as_ptr());
+
+                offset += text.len();
+            }
+            Section::Block(block) => {
+                let range = block.range(source);
+
+                assert_eq!(range.start, offset);
+                assert_eq!(Range::from(block.span), range);
+                assert!(source.get(range.clone()).is_some());
+
+                assert!(contains(&range, &block.content_range(source)));
+                assert!(contains(&range, &block.start_tag.span.into()));
+
+                for attribute in &block.start_tag.attributes {
+                    assert!(contains(&block.start_tag.span.into(), &attribute.span.into()));
+                }
+
+                offset = range.end;
+            }
+        }
+    }
+
+    assert_eq!(offset, source.len());
+}
+
+fn contains(outer: &Range<usize>, inner: &Range<usize>) -> bool {
+    outer.start <= inner.start && inner.end <= outer.end
+}
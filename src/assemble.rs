@@ -0,0 +1,630 @@
+use std::iter::{once, repeat_n};
+use std::ops::Range;
+
+use crate::configuration::{Configuration, ScriptSetupPosition};
+use crate::parser::line_break_ends;
+use crate::parser::Block;
+use crate::report::Normalization;
+
+/// A section of the file along with its formatted text.
+pub(crate) enum Unit<'a> {
+    Raw(&'a str),
+    /// A block along with its formatted content, see [`crate::format::format_block_content`].
+    Block {
+        block: Block<'a>,
+        content: String,
+    },
+}
+
+impl Unit<'_> {
+    fn len(&self) -> usize {
+        match self {
+            Unit::Raw(text) => text.len(),
+            Unit::Block { block, content } => block_len(block, content),
+        }
+    }
+}
+
+/// Where a block ended up in the output of [`assemble`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Placement {
+    /// Start of the block in the source, see [`Block::span`].
+    pub(crate) source_start: usize,
+    /// Byte range of the block in the output.
+    pub(crate) output: Range<usize>,
+    /// The normalizations which moved the block or merged it with another.
+    pub(crate) normalizations: Vec<Normalization>,
+}
+
+/// Apply the file level normalizations and concatenate the units, returning where each block ended
+/// up in the output.
+///
+/// Blocks merged into another `<style>` block end up where that block is.
+pub(crate) fn assemble(mut units: Vec<Unit>, config: &Configuration) -> (String, Vec<Placement>) {
+    let merges = if config.merge_duplicate_styles {
+        merge_duplicate_styles(&mut units)
+    } else {
+        Vec::new()
+    };
+
+    if !config.block_order.is_empty() {
+        reorder_blocks(&mut units, |block| {
+            let order = &config.block_order;
+
+            Some(
+                order
+                    .iter()
+                    .position(|name| name.eq_ignore_ascii_case(block.start_tag.name))
+                    .unwrap_or(order.len()),
+            )
+        });
+    }
+
+    if let Some(position) = config.script_setup_position {
+        reorder_blocks(&mut units, |block| {
+            if !block.start_tag.name.eq_ignore_ascii_case("script") {
+                return None;
+            }
+
+            Some(usize::from(
+                block.start_tag.setup() != (position == ScriptSetupPosition::First),
+            ))
+        });
+    }
+
+    // The blocks left after merging, in source order.
+    let mut starts: Vec<_> = units
+        .iter()
+        .filter_map(|unit| match unit {
+            Unit::Block { block, .. } => Some(block.span.start),
+            Unit::Raw(_) => None,
+        })
+        .collect();
+
+    starts.sort_unstable();
+
+    let mut buffer = String::with_capacity(units.iter().map(Unit::len).sum());
+    let mut placements = Vec::new();
+
+    let is_block = |index: usize| matches!(units.get(index), Some(Unit::Block { .. }));
+
+    for (index, unit) in units.iter().enumerate() {
+        let after_block = index > 0 && is_block(index - 1);
+
+        match unit {
+            Unit::Raw(text) if after_block && is_block(index + 1) => {
+                write_between_blocks(&mut buffer, text, config)
+            }
+            Unit::Raw(text) => write_raw(&mut buffer, text, config),
+            Unit::Block { block, content } => {
+                if after_block {
+                    write_between_blocks(&mut buffer, "", config);
+                }
+
+                let start = buffer.len();
+                write_block(&mut buffer, block, content, config);
+
+                let mut normalizations = Vec::new();
+
+                if starts[placements.len()] != block.span.start {
+                    normalizations.push(Normalization::Reordered);
+                }
+
+                if merges.iter().any(|&(_, target)| target == block.span.start) {
+                    normalizations.push(Normalization::Merged);
+                }
+
+                placements.push(Placement {
+                    source_start: block.span.start,
+                    output: start..buffer.len(),
+                    normalizations,
+                });
+            }
+        }
+    }
+
+    for (source_start, target) in merges {
+        let output = placements
+            .iter()
+            .find(|placement| placement.source_start == target)
+            .map(|placement| placement.output.clone())
+            .expect("the block merged into is written");
+
+        placements.push(Placement {
+            source_start,
+            output,
+            normalizations: vec![Normalization::Merged],
+        });
+    }
+
+    (buffer, placements)
+}
+
+/// Write the text separating two blocks, replacing it with `blankLinesBetweenBlocks` blank lines
+/// if it is only whitespace.
+pub(crate) fn write_between_blocks(buffer: &mut String, text: &str, config: &Configuration) {
+    match config.blank_lines_between_blocks {
+        Some(count) if text.trim().is_empty() => {
+            buffer.extend(repeat_n('\n', usize::from(count) + 1));
+        }
+        _ => write_raw(buffer, text, config),
+    }
+}
+
+/// Write text outside of blocks, trimming the end of its lines if `trimTrailingWhitespace` is
+/// set.
+pub(crate) fn write_raw(buffer: &mut String, text: &str, config: &Configuration) {
+    if config.trim_trailing_whitespace {
+        push_trimmed(buffer, text);
+    } else {
+        buffer.push_str(text);
+    }
+}
+
+/// Push `text` without the whitespace preceding its line breaks.
+fn push_trimmed(buffer: &mut String, text: &str) {
+    let mut start = 0;
+
+    for end in line_break_ends(text) {
+        let line = text[start..end].trim_end_matches(['\r', '\n']);
+
+        buffer.push_str(line.trim_end());
+        buffer.push_str(&text[start + line.len()..end]);
+        start = end;
+    }
+
+    buffer.push_str(&text[start..]);
+}
+
+/// Length of the text written by [`write_block`].
+pub(crate) fn block_len(block: &Block, content: &str) -> usize {
+    if block.start_tag.self_closing {
+        return block.raw_start_tag.len();
+    }
+
+    block.raw_start_tag.len() + 1 + content.len() + block.raw_end_tag.len()
+}
+
+/// Write the start tag of `block` on its own line, followed by `content` and the end tag.
+///
+/// Self-closing blocks are written as is, single-line blocks are kept on a single line if
+/// `keepSingleLineBlocks` is set.
+pub(crate) fn write_block(
+    buffer: &mut String,
+    block: &Block,
+    content: &str,
+    config: &Configuration,
+) {
+    let name_len = block.start_tag.name.len();
+
+    write_tag(buffer, block.raw_start_tag, 1..(1 + name_len), config);
+
+    if block.start_tag.self_closing {
+        return;
+    }
+
+    if config.keep_single_line_blocks
+        && !block.content.contains(['\r', '\n'])
+        && !content.trim().contains(['\r', '\n'])
+    {
+        buffer.push_str(content.trim());
+        write_tag(buffer, block.raw_end_tag, 2..(2 + name_len), config);
+        return;
+    }
+
+    buffer.push('\n');
+    buffer.push_str(content);
+    write_tag(buffer, block.raw_end_tag, 2..(2 + name_len), config);
+}
+
+/// Write a start or end tag, lowercasing the tag name at `name` if `lowercaseBlockTags` is set.
+fn write_tag(buffer: &mut String, tag: &str, name: Range<usize>, config: &Configuration) {
+    if !config.lowercase_block_tags {
+        write_tag_attributes(buffer, tag, config);
+        return;
+    }
+
+    buffer.push_str(&tag[..name.start]);
+    buffer.push_str(&tag[name.clone()].to_ascii_lowercase());
+    write_tag_attributes(buffer, &tag[name.end..], config);
+}
+
+/// Write the part of a tag following its name like [`write_raw`], keeping quoted attribute values,
+/// such as a multi-line `generic="..."`, as is.
+fn write_tag_attributes(buffer: &mut String, text: &str, config: &Configuration) {
+    if !config.trim_trailing_whitespace {
+        buffer.push_str(text);
+        return;
+    }
+
+    let mut start = 0;
+    let mut position = 0;
+
+    while let Some(equals) = text[position..].find('=') {
+        let value = text[position + equals + 1..]
+            .find(|char: char| !char.is_ascii_whitespace())
+            .map_or(text.len(), |offset| position + equals + 1 + offset);
+
+        position = match text[value..].chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = text[value + 1..]
+                    .find(quote)
+                    .map_or(text.len(), |offset| value + offset + 2);
+
+                push_trimmed(buffer, &text[start..value]);
+                buffer.push_str(&text[value..end]);
+                start = end;
+                end
+            }
+            _ => value,
+        };
+    }
+
+    push_trimmed(buffer, &text[start..]);
+}
+
+/// Key identifying `<style>` blocks that can be merged, the start tag with normalized whitespace.
+fn style_key(block: &Block) -> Option<String> {
+    if !block.start_tag.name.eq_ignore_ascii_case("style") || block.start_tag.self_closing {
+        return None;
+    }
+
+    let tag = block
+        .raw_start_tag
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .trim_end_matches('/');
+
+    Some(tag.split_ascii_whitespace().collect::<Vec<_>>().join(" "))
+}
+
+/// Sort the blocks with a rank by it, the blocks without one stay in place, see `blockOrder` and
+/// `scriptSetupPosition`.
+///
+/// The lines directly above a block, such as comments, move with it while the text separating the
+/// blocks stays in place.
+fn reorder_blocks(units: &mut Vec<Unit>, rank: impl Fn(&Block) -> Option<usize>) {
+    let mut separators = vec![Vec::new()];
+    let mut groups = Vec::new();
+    let mut after_block = false;
+
+    for unit in units.drain(..) {
+        let rank = match &unit {
+            Unit::Block { block, .. } => rank(block),
+            Unit::Raw(_) => None,
+        };
+
+        let separator = separators.last_mut().unwrap();
+
+        let rank = match rank {
+            Some(rank) => rank,
+            None => {
+                after_block |= matches!(unit, Unit::Block { .. });
+                separator.push(unit);
+                continue;
+            }
+        };
+
+        let mut group = Vec::new();
+
+        if let Some(&Unit::Raw(text)) = separator.last() {
+            let start = attached_start(text, after_block);
+
+            if start < text.len() {
+                separator.pop();
+
+                if start > 0 {
+                    separator.push(Unit::Raw(&text[..start]));
+                }
+
+                group.push(Unit::Raw(&text[start..]));
+            }
+        }
+
+        group.push(unit);
+        groups.push((rank, group));
+        separators.push(Vec::new());
+        after_block = true;
+    }
+
+    groups.sort_by_key(|(rank, _)| *rank);
+
+    let mut separators = separators.into_iter();
+
+    for (_, group) in groups {
+        units.extend(separators.next().unwrap());
+        units.extend(group);
+    }
+
+    units.extend(separators.flatten());
+}
+
+/// Offset of the first of the non-blank lines at the end of `text`, the text preceding a block.
+///
+/// The first line is the line of the previous end tag if `after_block` is set, it is never part of
+/// the result.
+fn attached_start(text: &str, after_block: bool) -> usize {
+    let starts: Vec<_> = once(0).chain(line_break_ends(text)).collect();
+    let mut start = starts[starts.len() - 1];
+
+    for line in starts.windows(2).rev() {
+        if text[line[0]..line[1]].trim().is_empty() || (after_block && line[0] == 0) {
+            break;
+        }
+
+        start = line[0];
+    }
+
+    start
+}
+
+/// Append the content of `<style>` blocks to the first `<style>` block with the same attributes.
+///
+/// Blocks are only merged when no other `<style>` block is between them, so that the order of the
+/// rules, and thus the cascade, is kept. Returns the start of each removed block along with the
+/// start of the block it was merged into, see [`Block::span`].
+fn merge_duplicate_styles(units: &mut Vec<Unit>) -> Vec<(usize, usize)> {
+    let mut merges = Vec::new();
+    let mut index = 0;
+
+    while index < units.len() {
+        let key = match &units[index] {
+            Unit::Block { block, .. } => style_key(block),
+            Unit::Raw(_) => None,
+        };
+
+        let key = match key {
+            Some(key) => key,
+            None => {
+                index += 1;
+                continue;
+            }
+        };
+
+        let mut other = index + 1;
+
+        while other < units.len() {
+            let other_key = match &units[other] {
+                Unit::Block { block, .. } => style_key(block),
+                Unit::Raw(_) => None,
+            };
+
+            match other_key {
+                Some(other_key) if other_key == key => {}
+                Some(_) => break,
+                None => {
+                    other += 1;
+                    continue;
+                }
+            }
+
+            let (removed_start, removed_content) = match units.remove(other) {
+                Unit::Block { block, content } => (block.span.start, content),
+                Unit::Raw(_) => unreachable!(),
+            };
+
+            // Drop the whitespace which separated the removed block from the previous unit.
+            if other - 1 > index
+                && matches!(units[other - 1], Unit::Raw(text) if text.trim().is_empty())
+            {
+                units.remove(other - 1);
+                other -= 1;
+            }
+
+            if let Unit::Block { block, content } = &mut units[index] {
+                merges.push((removed_start, block.span.start));
+
+                if !removed_content.is_empty() {
+                    if !content.is_empty() && !content.ends_with('\n') {
+                        content.push('\n');
+                    }
+
+                    content.push_str(&removed_content);
+                }
+            }
+        }
+
+        index += 1;
+    }
+
+    merges
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dprint_core::configuration::ConfigKeyMap;
+
+    use crate::configuration::{Configuration, ScriptSetupPosition};
+    use crate::format::format;
+
+    use super::{attached_start, push_trimmed};
+
+    #[test]
+    fn test_attached_start() {
+        assert_eq!(attached_start("\n\n<!-- a -->\n<!-- b -->\n", true), 2);
+        assert_eq!(attached_start(" <!-- a -->\n", true), 12);
+        assert_eq!(attached_start("<!-- a -->\n", false), 0);
+        assert_eq!(attached_start("<!-- a -->\n\n", false), 12);
+        assert_eq!(attached_start("\n", true), 1);
+    }
+
+    #[test]
+    fn test_block_order() {
+        let raw = concat!(
+            "<!-- file -->\n\n",
+            "<template>\n<div></div>\n</template>\n\n",
+            "<!-- a -->\n<style>\na {}\n</style>\n\n",
+            "<i18n>\n{}\n</i18n>\n\n",
+            "<!-- b -->\n<Script>\nlet a;\n</Script>\n",
+        );
+
+        let config = Configuration {
+            indent_template: false,
+            block_order: vec![
+                String::from("script"),
+                String::from("template"),
+                String::from("style"),
+            ],
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            concat!(
+                "<!-- file -->\n\n",
+                "<!-- b -->\n<Script>\nlet a;\n</Script>\n\n",
+                "<template>\n<div></div>\n</template>\n\n",
+                "<!-- a -->\n<style>\na {}\n</style>\n\n",
+                "<i18n>\n{}\n</i18n>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_push_trimmed() {
+        let mut buffer = String::new();
+        push_trimmed(&mut buffer, "a \t\r\n \n\tb  ");
+
+        assert_eq!(buffer, "a\r\n\n\tb  ");
+    }
+
+    #[test]
+    fn test_blank_lines_between_blocks() {
+        let raw = "<!-- a -->\n\n\n<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n\n\n\n<style>\na {}\n</style>\n\n";
+        let format_with_host = |_: &Path, raw: String, _: &ConfigKeyMap| Ok(raw);
+
+        let config = Configuration {
+            indent_template: false,
+            blank_lines_between_blocks: Some(1),
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            "<!-- a -->\n\n\n<template>\n<div></div>\n</template>\n\n<script>\nlet a;\n</script>\n\n<style>\na {}\n</style>\n\n"
+        );
+
+        let config = Configuration {
+            blank_lines_between_blocks: None,
+            ..config
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            raw
+        );
+    }
+
+    #[test]
+    fn test_script_setup_position() {
+        let raw = concat!(
+            "<script setup>\nlet a;\n</script>\n\n",
+            "<template>\n<div></div>\n</template>\n\n",
+            "<!-- b -->\n<script>\nexport default {};\n</script>\n",
+        );
+
+        let config = Configuration {
+            indent_template: false,
+            script_setup_position: Some(ScriptSetupPosition::Last),
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            concat!(
+                "<!-- b -->\n<script>\nexport default {};\n</script>\n\n",
+                "<template>\n<div></div>\n</template>\n\n",
+                "<script setup>\nlet a;\n</script>\n",
+            )
+        );
+
+        let config = Configuration {
+            script_setup_position: Some(ScriptSetupPosition::First),
+            ..config
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            raw
+        );
+    }
+
+    #[test]
+    fn test_keep_single_line_blocks() {
+        let raw = "<script>export default {}</script>\n<style></style>\n<i18n>{}\n</i18n>\n";
+        let format_with_host = |_: &Path, raw: String, _: &ConfigKeyMap| Ok(raw);
+
+        let config = Configuration {
+            keep_single_line_blocks: true,
+            indent_script_and_style: true,
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            "<script>export default {}</script>\n<style></style>\n<i18n>\n{}\n</i18n>\n"
+        );
+
+        let config = Configuration {
+            keep_single_line_blocks: false,
+            ..config
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            "<script>\n    export default {}\n</script>\n<style>\n</style>\n<i18n>\n{}\n</i18n>\n"
+        );
+    }
+
+    #[test]
+    fn test_trim_trailing_whitespace() {
+        let raw = "<!-- a -->  \n<script  \n  setup  \n  generic=\"T extends A,  \n    U\"  \n>\nlet a;\n</script>  \n";
+        let format_with_host = |_: &Path, raw: String, _: &ConfigKeyMap| Ok(raw);
+
+        let config = Configuration::default();
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            "<!-- a -->\n<script\n  setup\n  generic=\"T extends A,  \n    U\"\n>\nlet a;\n</script>\n"
+        );
+
+        let config = Configuration {
+            trim_trailing_whitespace: false,
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            raw
+        );
+    }
+
+    #[test]
+    fn test_merge_duplicate_styles() {
+        let config = Configuration {
+            merge_duplicate_styles: true,
+            ..Configuration::default()
+        };
+
+        let raw = concat!(
+            "<style scoped>\n.a {}\n</style>\n\n",
+            "<template><div></div></template>\n\n",
+            "<style  scoped >\n.b {}\n</style>\n\n",
+            "<style lang=\"scss\">\n.c {}\n</style>\n\n",
+            "<style scoped>\n.d {}\n</style>\n",
+        );
+
+        // `.d` comes after `.c` in the cascade, it is not merged before it.
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            concat!(
+                "<style scoped>\n.a {}\n.b {}\n</style>\n\n",
+                "<template>\n    <div></div>\n</template>\n\n",
+                "<style lang=\"scss\">\n.c {}\n</style>\n\n",
+                "<style scoped>\n.d {}\n</style>\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,200 @@
+use anyhow::{bail, Result};
+
+use crate::configuration::Configuration;
+use crate::descriptor::SfcDescriptor;
+use crate::format::with_file_normalizations;
+use crate::parser::offset_of;
+
+/// A block added to an [`SfcBuilder`].
+#[derive(Debug, Clone, PartialEq)]
+struct BlockSpec {
+    name: String,
+    attributes: Vec<(String, Option<String>)>,
+    content: String,
+}
+
+/// Build the source of a Vue SFC from its blocks, laid out the way the formatter would.
+///
+/// # Example
+///
+/// ```
+/// use dprint_plugin_vue::configuration::ConfigurationBuilder;
+/// use dprint_plugin_vue::SfcBuilder;
+///
+/// let config = ConfigurationBuilder::new().indent_width(2).build();
+///
+/// let source = SfcBuilder::new(&config)
+///     .block("template", &[], "<div></div>")
+///     .block("script", &[("setup", None), ("lang", Some("ts"))], "let a;")
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     source,
+///     "<template>\n  <div></div>\n</template>\n\n<script setup lang=\"ts\">\nlet a;\n</script>\n"
+/// );
+/// ```
+#[derive(Debug, Clone)]
+pub struct SfcBuilder<'c> {
+    config: &'c Configuration,
+    blocks: Vec<BlockSpec>,
+}
+
+impl<'c> SfcBuilder<'c> {
+    pub fn new(config: &'c Configuration) -> Self {
+        Self {
+            config,
+            blocks: Vec::new(),
+        }
+    }
+
+    /// Add a block, attributes without a value are written as `name` rather than `name=""`.
+    pub fn block(
+        &mut self,
+        name: &str,
+        attributes: &[(&str, Option<&str>)],
+        content: &str,
+    ) -> &mut Self {
+        self.blocks.push(BlockSpec {
+            name: String::from(name),
+            attributes: attributes
+                .iter()
+                .map(|(name, value)| (String::from(*name), value.map(String::from)))
+                .collect(),
+            content: String::from(content),
+        });
+
+        self
+    }
+
+    /// Emit the blocks, laid out and separated according to the configuration.
+    ///
+    /// Fails if the content of a block contains its end tag.
+    pub fn build(&self) -> Result<String> {
+        let mut source = String::new();
+        let mut contents = Vec::with_capacity(self.blocks.len());
+
+        for (index, block) in self.blocks.iter().enumerate() {
+            if index > 0 {
+                source.push('\n');
+            }
+
+            source.push('<');
+            source.push_str(&block.name);
+
+            for (name, value) in &block.attributes {
+                source.push(' ');
+                source.push_str(name);
+
+                if let Some(value) = value {
+                    if value.contains('"') && !value.contains('\'') {
+                        source.push_str(&format!("='{value}'"));
+                    } else {
+                        source.push_str(&format!("=\"{}\"", value.replace('"', "&quot;")));
+                    }
+                }
+            }
+
+            source.push_str(">\n");
+
+            let start = source.len();
+            let content = block.content.trim_start_matches(['\r', '\n']).trim_end();
+
+            if !content.is_empty() {
+                source.push_str(content);
+                source.push('\n');
+            }
+
+            contents.push(start..source.len());
+
+            source.push_str("</");
+            source.push_str(&block.name);
+            source.push_str(">\n");
+        }
+
+        with_file_normalizations(&source, self.config, false, |source| {
+            let descriptor = SfcDescriptor::parse_with(source, &self.config.parse_options())?;
+            let mut blocks = descriptor.blocks();
+
+            for (spec, content) in self.blocks.iter().zip(&contents) {
+                let is_intact = blocks.next().is_some_and(|block| {
+                    offset_of(source, block.content) == content.start
+                        && block.content.len() == content.len()
+                });
+
+                if !is_intact {
+                    bail!(
+                        "The content of the `<{}>` block contains its end tag.",
+                        spec.name
+                    );
+                }
+            }
+
+            Ok(descriptor.emit(self.config))
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::configuration::{Configuration, NewLineKind};
+
+    use super::SfcBuilder;
+
+    #[test]
+    fn test_build() {
+        let config = Configuration {
+            indent_template: false,
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            SfcBuilder::new(&config)
+                .block("template", &[], "<div></div>\n")
+                .block("style", &[("scoped", None)], "")
+                .block("i18n", &[("locale", Some(r#"say "hi""#))], "{}")
+                .build()
+                .unwrap(),
+            "<template>\n<div></div>\n</template>\n\n<style scoped>\n</style>\n\n<i18n locale='say \"hi\"'>\n{}\n</i18n>\n"
+        );
+    }
+
+    #[test]
+    fn test_build_config() {
+        let config = Configuration {
+            indent_width: 2,
+            new_line_kind: Some(NewLineKind::Crlf),
+            lowercase_block_tags: true,
+            blank_lines_between_blocks: Some(0),
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            SfcBuilder::new(&config)
+                .block("Template", &[], "<div>\n  <p></p>\n</div>")
+                .block("script", &[], "let a;")
+                .build()
+                .unwrap(),
+            "<template>\r\n  <div>\r\n    <p></p>\r\n  </div>\r\n</template>\r\n<script>\r\nlet a;\r\n</script>\r\n"
+        );
+    }
+
+    #[test]
+    fn test_build_end_tag() {
+        let config = Configuration::default();
+
+        assert_eq!(
+            SfcBuilder::new(&config)
+                .block("style", &[], "a {}\n</style>\n<style>")
+                .build()
+                .unwrap_err()
+                .to_string(),
+            "The content of the `<style>` block contains its end tag."
+        );
+
+        assert!(SfcBuilder::new(&config)
+            .block("script", &[], "let a = '</script>';")
+            .build()
+            .is_ok());
+    }
+}
@@ -0,0 +1,101 @@
+use std::borrow::Cow;
+use std::path::Path;
+
+use anyhow::Result;
+use dprint_core::configuration::ConfigKeyMap;
+
+use crate::configuration::Configuration;
+use crate::format::format;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BytesFormatResult {
+    /// The formatted text, starting with a byte order mark if the content had one and
+    /// `keepByteOrderMark` is enabled.
+    pub text: String,
+    /// Set when invalid UTF-8 sequences were replaced in lossy mode.
+    pub warning: Option<String>,
+}
+
+/// Same as [`crate::format`] for content of unknown encoding.
+///
+/// A leading byte order mark is handled according to `keepByteOrderMark`. Content which is not valid UTF-8 is rejected unless
+/// `lossy` is set, in which case invalid sequences are replaced with U+FFFD and a warning is
+/// returned.
+pub fn format_bytes(
+    path: &Path,
+    content: &[u8],
+    config: &Configuration,
+    lossy: bool,
+    format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
+) -> Result<BytesFormatResult> {
+    let (content, warning) = match std::str::from_utf8(content) {
+        Ok(content) => (Cow::Borrowed(content), None),
+        Err(err) if !lossy => anyhow::bail!("Content is not valid UTF-8: {err}."),
+        Err(err) => (
+            String::from_utf8_lossy(content),
+            Some(format!(
+                "Content is not valid UTF-8, invalid sequences were replaced: {err}."
+            )),
+        ),
+    };
+
+    Ok(BytesFormatResult {
+        text: format(path, &content, config, format_with_host)?,
+        warning,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::configuration::Configuration;
+
+    use super::format_bytes;
+
+    #[test]
+    fn test_format_bytes() {
+        let config = Configuration::default();
+        let path = Path::new("file.vue");
+
+        let result = format_bytes(
+            path,
+            b"\xef\xbb\xbf<script>\na\n</script>\n",
+            &config,
+            false,
+            |_, raw, _| Ok(raw),
+        )
+        .unwrap();
+
+        assert_eq!(result.text, "\u{feff}<script>\na\n</script>\n");
+        assert_eq!(result.warning, None);
+
+        let result = format_bytes(
+            path,
+            b"\xef\xbb\xbf<script>\na\n</script>\n",
+            &Configuration {
+                keep_byte_order_mark: false,
+                ..Configuration::default()
+            },
+            false,
+            |_, raw, _| Ok(raw),
+        )
+        .unwrap();
+
+        assert_eq!(result.text, "<script>\na\n</script>\n");
+
+        let raw = b"<script>\n'\xff'\n</script>\n";
+
+        assert_eq!(
+            format_bytes(path, raw, &config, false, |_, raw, _| Ok(raw))
+                .unwrap_err()
+                .to_string(),
+            "Content is not valid UTF-8: invalid utf-8 sequence of 1 bytes from index 10."
+        );
+
+        let result = format_bytes(path, raw, &config, true, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(result.text, "<script>\n'\u{fffd}'\n</script>\n");
+        assert!(result.warning.is_some());
+    }
+}
@@ -1,17 +1,139 @@
 use dprint_core::configuration::get_unknown_property_diagnostics;
-use dprint_core::configuration::get_value;
+use dprint_core::configuration::resolve_new_line_kind;
 use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigurationDiagnostic;
 use dprint_core::configuration::GlobalConfiguration;
 use dprint_core::configuration::ResolveConfigurationResult;
 use dprint_core::configuration::DEFAULT_GLOBAL_CONFIGURATION;
 use serde::Serialize;
 
-#[derive(Debug, Clone, Copy, Serialize)]
+use crate::indentation::Indentation;
+use crate::parser::ParseOptions;
+
+mod builder;
+mod value;
+
+pub use builder::ConfigurationBuilder;
+
+use value::{get_nullable_value, get_value};
+
+/// Versions `compatVersion` can be set to.
+const COMPAT_VERSIONS: &[&str] = &["0.3"];
+
+/// Line ending of the formatted file, see `newLineKind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NewLineKind {
+    /// The last line ending of the file.
+    Auto,
+    Lf,
+    Crlf,
+    /// `crlf` on Windows, `lf` elsewhere.
+    System,
+}
+
+impl NewLineKind {
+    /// Return the configuration value of the line ending, such as `"lf"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Lf => "lf",
+            Self::Crlf => "crlf",
+            Self::System => "system",
+        }
+    }
+
+    /// Return the line break to use for the formatted `text`.
+    pub fn resolve(self, text: &str) -> &'static str {
+        resolve_new_line_kind(text, self.into())
+    }
+}
+
+/// Position of the `<script setup>` block among the `<script>` blocks, see `scriptSetupPosition`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScriptSetupPosition {
+    First,
+    Last,
+}
+
+impl ScriptSetupPosition {
+    /// Return the configuration value of the position, such as `"first"`.
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::First => "first",
+            Self::Last => "last",
+        }
+    }
+}
+
+impl From<dprint_core::configuration::NewLineKind> for NewLineKind {
+    fn from(kind: dprint_core::configuration::NewLineKind) -> Self {
+        match kind {
+            dprint_core::configuration::NewLineKind::Auto => Self::Auto,
+            dprint_core::configuration::NewLineKind::LineFeed => Self::Lf,
+            dprint_core::configuration::NewLineKind::CarriageReturnLineFeed => Self::Crlf,
+            dprint_core::configuration::NewLineKind::System => Self::System,
+        }
+    }
+}
+
+impl From<NewLineKind> for dprint_core::configuration::NewLineKind {
+    fn from(kind: NewLineKind) -> Self {
+        match kind {
+            NewLineKind::Auto => Self::Auto,
+            NewLineKind::Lf => Self::LineFeed,
+            NewLineKind::Crlf => Self::CarriageReturnLineFeed,
+            NewLineKind::System => Self::System,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Configuration {
     pub indent_template: bool,
     pub use_tabs: bool,
     pub indent_width: u8,
+    pub line_width: u32,
+    pub new_line_kind: Option<NewLineKind>,
+    #[serde(rename = "template.maxBlankLines")]
+    pub template_max_blank_lines: u8,
+    #[serde(rename = "script.defaultLang")]
+    pub script_default_lang: String,
+    #[serde(rename = "template.defaultLang")]
+    pub template_default_lang: String,
+    #[serde(rename = "style.defaultLang")]
+    pub style_default_lang: String,
+    pub minified_threshold: u32,
+    pub merge_duplicate_styles: bool,
+    pub template_body_indent: bool,
+    #[serde(rename = "template.pipeline")]
+    pub template_pipeline: Vec<String>,
+    #[serde(rename = "script.pipeline")]
+    pub script_pipeline: Vec<String>,
+    #[serde(rename = "style.pipeline")]
+    pub style_pipeline: Vec<String>,
+    pub auto_close_final_block: bool,
+    pub compat_version: Option<String>,
+    pub lowercase_block_tags: bool,
+    pub max_nesting_depth: u16,
+    pub keep_byte_order_mark: bool,
+    pub blank_file_newline: bool,
+    pub indent_script_and_style: bool,
+    #[serde(rename = "template.indentContent")]
+    pub template_indent_content: Option<bool>,
+    #[serde(rename = "script.indentContent")]
+    pub script_indent_content: Option<bool>,
+    #[serde(rename = "style.indentContent")]
+    pub style_indent_content: Option<bool>,
+    pub final_newline: bool,
+    pub trim_trailing_whitespace: bool,
+    pub trim_block_content: bool,
+    pub blank_lines_between_blocks: Option<u8>,
+    pub block_order: Vec<String>,
+    pub script_setup_position: Option<ScriptSetupPosition>,
+    pub keep_single_line_blocks: bool,
 }
 
 impl Default for Configuration {
@@ -20,17 +142,95 @@ impl Default for Configuration {
             indent_template: true,
             use_tabs: DEFAULT_GLOBAL_CONFIGURATION.use_tabs,
             indent_width: DEFAULT_GLOBAL_CONFIGURATION.indent_width,
+            line_width: DEFAULT_GLOBAL_CONFIGURATION.line_width,
+            new_line_kind: Some(DEFAULT_GLOBAL_CONFIGURATION.new_line_kind.into()),
+            template_max_blank_lines: 1,
+            script_default_lang: String::from("js"),
+            template_default_lang: String::from("html"),
+            style_default_lang: String::from("css"),
+            minified_threshold: 0,
+            merge_duplicate_styles: false,
+            template_body_indent: true,
+            template_pipeline: Vec::new(),
+            script_pipeline: Vec::new(),
+            style_pipeline: Vec::new(),
+            auto_close_final_block: false,
+            compat_version: None,
+            lowercase_block_tags: false,
+            max_nesting_depth: 256,
+            keep_byte_order_mark: true,
+            blank_file_newline: false,
+            indent_script_and_style: false,
+            template_indent_content: None,
+            script_indent_content: None,
+            style_indent_content: None,
+            final_newline: false,
+            trim_trailing_whitespace: true,
+            trim_block_content: false,
+            blank_lines_between_blocks: None,
+            block_order: Vec::new(),
+            script_setup_position: None,
+            keep_single_line_blocks: false,
         }
     }
 }
 
 impl Configuration {
+    /// Options used to parse files formatted with this configuration.
+    pub(crate) fn parse_options(&self) -> ParseOptions {
+        ParseOptions {
+            max_nesting_depth: self.max_nesting_depth,
+        }
+    }
+
+    /// Whether `compatVersion` keeps the formatting of version 0.3.
+    pub(crate) fn is_v0_3(&self) -> bool {
+        self.compat_version.as_deref() == Some("0.3")
+    }
+
+    /// One level of indentation, a tab is `indentWidth` columns wide.
+    ///
+    /// Version 0.3 indented with `indentWidth` tabs.
+    pub(crate) fn indentation(&self) -> Indentation {
+        if self.use_tabs && !self.is_v0_3() {
+            Indentation::new(true, 1)
+        } else {
+            Indentation::new(self.use_tabs, usize::from(self.indent_width))
+        }
+    }
+
     pub(crate) fn resolve(
         mut config: ConfigKeyMap,
         global_config: &GlobalConfiguration,
     ) -> ResolveConfigurationResult<Configuration> {
         let mut diagnostics = Vec::new();
 
+        // Presets are resolved by dprint, which knows where the configuration file is and can
+        // download them.
+        if config.remove("extends").is_some() {
+            diagnostics.push(ConfigurationDiagnostic {
+                property_name: String::from("extends"),
+                message: String::from(
+                    "Set `extends` at the top level of the dprint configuration file, it accepts a path or a URL to shared options.",
+                ),
+            });
+        }
+
+        let compat_version =
+            match get_nullable_value::<String>(&mut config, "compatVersion", &mut diagnostics) {
+                Some(version) if !COMPAT_VERSIONS.contains(&version.as_str()) => {
+                    diagnostics.push(ConfigurationDiagnostic {
+                        property_name: String::from("compatVersion"),
+                        message: format!("Unsupported `compatVersion` {version:?}."),
+                    });
+
+                    None
+                }
+                version => version,
+            };
+
+        let is_v0_3 = compat_version.as_deref() == Some("0.3");
+
         let resolved_config = Configuration {
             indent_template: get_value(&mut config, "indentTemplate", true, &mut diagnostics),
             use_tabs: get_value(
@@ -49,6 +249,144 @@ impl Configuration {
                     .unwrap_or(DEFAULT_GLOBAL_CONFIGURATION.indent_width),
                 &mut diagnostics,
             ),
+            line_width: get_value(
+                &mut config,
+                "lineWidth",
+                global_config
+                    .line_width
+                    .unwrap_or(DEFAULT_GLOBAL_CONFIGURATION.line_width),
+                &mut diagnostics,
+            ),
+            new_line_kind: if is_v0_3 {
+                get_nullable_value(&mut config, "newLineKind", &mut diagnostics)
+            } else {
+                Some(get_value(
+                    &mut config,
+                    "newLineKind",
+                    global_config
+                        .new_line_kind
+                        .unwrap_or(DEFAULT_GLOBAL_CONFIGURATION.new_line_kind)
+                        .into(),
+                    &mut diagnostics,
+                ))
+            },
+            template_max_blank_lines: get_value(
+                &mut config,
+                "template.maxBlankLines",
+                if is_v0_3 { u8::MAX } else { 1 },
+                &mut diagnostics,
+            ),
+            script_default_lang: get_value(
+                &mut config,
+                "script.defaultLang",
+                String::from("js"),
+                &mut diagnostics,
+            ),
+            template_default_lang: get_value(
+                &mut config,
+                "template.defaultLang",
+                String::from("html"),
+                &mut diagnostics,
+            ),
+            style_default_lang: get_value(
+                &mut config,
+                "style.defaultLang",
+                String::from("css"),
+                &mut diagnostics,
+            ),
+            minified_threshold: get_value(&mut config, "minifiedThreshold", 0, &mut diagnostics),
+            merge_duplicate_styles: get_value(
+                &mut config,
+                "mergeDuplicateStyles",
+                false,
+                &mut diagnostics,
+            ),
+            template_body_indent: get_value(
+                &mut config,
+                "templateBodyIndent",
+                true,
+                &mut diagnostics,
+            ),
+            template_pipeline: get_value(
+                &mut config,
+                "template.pipeline",
+                Vec::new(),
+                &mut diagnostics,
+            ),
+            script_pipeline: get_value(
+                &mut config,
+                "script.pipeline",
+                Vec::new(),
+                &mut diagnostics,
+            ),
+            style_pipeline: get_value(&mut config, "style.pipeline", Vec::new(), &mut diagnostics),
+            auto_close_final_block: get_value(
+                &mut config,
+                "autoCloseFinalBlock",
+                false,
+                &mut diagnostics,
+            ),
+            compat_version,
+            lowercase_block_tags: get_value(
+                &mut config,
+                "lowercaseBlockTags",
+                false,
+                &mut diagnostics,
+            ),
+            max_nesting_depth: get_value(&mut config, "maxNestingDepth", 256, &mut diagnostics),
+            keep_byte_order_mark: get_value(
+                &mut config,
+                "keepByteOrderMark",
+                true,
+                &mut diagnostics,
+            ),
+            blank_file_newline: get_value(&mut config, "blankFileNewline", false, &mut diagnostics),
+            indent_script_and_style: get_value(
+                &mut config,
+                "indentScriptAndStyle",
+                false,
+                &mut diagnostics,
+            ),
+            template_indent_content: get_nullable_value(
+                &mut config,
+                "template.indentContent",
+                &mut diagnostics,
+            ),
+            script_indent_content: get_nullable_value(
+                &mut config,
+                "script.indentContent",
+                &mut diagnostics,
+            ),
+            style_indent_content: get_nullable_value(
+                &mut config,
+                "style.indentContent",
+                &mut diagnostics,
+            ),
+            final_newline: get_value(&mut config, "finalNewline", false, &mut diagnostics),
+            trim_trailing_whitespace: get_value(
+                &mut config,
+                "trimTrailingWhitespace",
+                !is_v0_3,
+                &mut diagnostics,
+            ),
+            trim_block_content: get_value(&mut config, "trimBlockContent", false, &mut diagnostics),
+            blank_lines_between_blocks: get_nullable_value(
+                &mut config,
+                "blankLinesBetweenBlocks",
+                &mut diagnostics,
+            ),
+            block_order: get_value(&mut config, "blockOrder", Vec::new(), &mut diagnostics),
+            script_setup_position: get_nullable_value(
+                &mut config,
+                "scriptSetupPosition",
+                &mut diagnostics,
+            ),
+            keep_single_line_blocks: get_value(
+                &mut config,
+                "keepSingleLineBlocks",
+                false,
+                &mut diagnostics,
+            ),
         };
 
         diagnostics.extend(get_unknown_property_diagnostics(config));
@@ -59,3 +397,93 @@ impl Configuration {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use dprint_core::configuration::{ConfigKeyValue, GlobalConfiguration};
+
+    use super::{Configuration, NewLineKind};
+
+    #[test]
+    fn test_extends() {
+        let config = HashMap::from([(
+            String::from("extends"),
+            ConfigKeyValue::from_str("https://example.com/vue.json"),
+        )]);
+
+        let global_config = GlobalConfiguration {
+            line_width: None,
+            use_tabs: None,
+            indent_width: None,
+            new_line_kind: None,
+        };
+
+        let result = Configuration::resolve(config, &global_config);
+
+        assert_eq!(result.diagnostics.len(), 1);
+        assert_eq!(result.diagnostics[0].property_name, "extends");
+    }
+
+    #[test]
+    fn test_compat_version() {
+        let global_config = GlobalConfiguration {
+            line_width: None,
+            use_tabs: None,
+            indent_width: None,
+            new_line_kind: None,
+        };
+
+        let config = HashMap::from([(
+            String::from("compatVersion"),
+            ConfigKeyValue::from_str("0.3"),
+        )]);
+
+        let result = Configuration::resolve(config, &global_config);
+
+        assert!(result.diagnostics.is_empty());
+        assert_eq!(result.config.template_max_blank_lines, u8::MAX);
+        assert_eq!(result.config.new_line_kind, None);
+        assert!(!result.config.trim_trailing_whitespace);
+
+        let config = HashMap::from([(
+            String::from("compatVersion"),
+            ConfigKeyValue::from_str("0.1"),
+        )]);
+
+        let result = Configuration::resolve(config, &global_config);
+
+        assert_eq!(result.config.compat_version, None);
+        assert_eq!(result.config.template_max_blank_lines, 1);
+        assert_eq!(result.config.new_line_kind, Some(NewLineKind::Lf));
+        assert!(result.config.trim_trailing_whitespace);
+        assert_eq!(
+            result.diagnostics[0].message,
+            "Unsupported `compatVersion` \"0.1\"."
+        );
+    }
+
+    #[test]
+    fn test_max_nesting_depth() {
+        let config = HashMap::from([(
+            String::from("maxNestingDepth"),
+            ConfigKeyValue::from_i32(70000),
+        )]);
+
+        let global_config = GlobalConfiguration {
+            line_width: None,
+            use_tabs: None,
+            indent_width: None,
+            new_line_kind: None,
+        };
+
+        let result = Configuration::resolve(config, &global_config);
+
+        assert_eq!(result.config.max_nesting_depth, 256);
+        assert_eq!(
+            result.diagnostics[0].message,
+            "Expected an integer between 0 and 65535 for `maxNestingDepth`, received the number `70000`."
+        );
+    }
+}
@@ -0,0 +1,356 @@
+use std::collections::HashMap;
+
+use dprint_core::configuration::resolve_global_config;
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::configuration::GlobalConfiguration;
+
+use super::{Configuration, NewLineKind, ScriptSetupPosition};
+
+/// Formatting configuration builder.
+///
+/// # Example
+///
+/// ```
+/// use dprint_plugin_vue::configuration::ConfigurationBuilder;
+///
+/// let config = ConfigurationBuilder::new()
+///     .indent_template(false)
+///     .indent_width(4)
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct ConfigurationBuilder {
+    config: ConfigKeyMap,
+    global_config: Option<GlobalConfiguration>,
+}
+
+impl ConfigurationBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Resolve the configuration, ignoring diagnostics.
+    pub fn build(&self) -> Configuration {
+        let global_config = match &self.global_config {
+            Some(global_config) => global_config.clone(),
+            None => resolve_global_config(HashMap::new(), &Default::default()).config,
+        };
+
+        Configuration::resolve(self.config.clone(), &global_config).config
+    }
+
+    /// Set the global configuration to resolve defaults from.
+    pub fn global_config(&mut self, global_config: GlobalConfiguration) -> &mut Self {
+        self.global_config = Some(global_config);
+        self
+    }
+
+    /// Indent the content of the `<template>` tag.
+    ///
+    /// Default: `true`
+    pub fn indent_template(&mut self, value: bool) -> &mut Self {
+        self.insert("indentTemplate", value.into())
+    }
+
+    /// Use tabs for indentation.
+    ///
+    /// Default: `false`
+    pub fn use_tabs(&mut self, value: bool) -> &mut Self {
+        self.insert("useTabs", value.into())
+    }
+
+    /// Width of the indentation.
+    ///
+    /// Default: `2`
+    pub fn indent_width(&mut self, value: u8) -> &mut Self {
+        self.insert("indentWidth", i32::from(value).into())
+    }
+
+    /// Width of a line, blocks indented by this plugin are formatted with the indentation
+    /// subtracted from it.
+    ///
+    /// Default: `120`
+    pub fn line_width(&mut self, value: u32) -> &mut Self {
+        self.insert("lineWidth", i32::try_from(value).unwrap_or(i32::MAX).into())
+    }
+
+    /// Line ending of the formatted file.
+    ///
+    /// Default: `NewLineKind::Lf`, line endings are kept as is with `compatVersion` `"0.3"`
+    pub fn new_line_kind(&mut self, value: NewLineKind) -> &mut Self {
+        self.insert("newLineKind", value.as_str().into())
+    }
+
+    /// Maximum number of consecutive blank lines in the content of the `<template>` tag.
+    ///
+    /// Default: `1`, not limited with `compatVersion` `"0.3"`
+    pub fn template_max_blank_lines(&mut self, value: u8) -> &mut Self {
+        self.insert("template.maxBlankLines", i32::from(value).into())
+    }
+
+    /// Lang of `<script>` tags without a `lang` attribute.
+    ///
+    /// Default: `"js"`
+    pub fn script_default_lang(&mut self, value: &str) -> &mut Self {
+        self.insert("script.defaultLang", value.into())
+    }
+
+    /// Lang of `<template>` tags without a `lang` attribute.
+    ///
+    /// Default: `"html"`
+    pub fn template_default_lang(&mut self, value: &str) -> &mut Self {
+        self.insert("template.defaultLang", value.into())
+    }
+
+    /// Lang of `<style>` tags without a `lang` attribute.
+    ///
+    /// Default: `"css"`
+    pub fn style_default_lang(&mut self, value: &str) -> &mut Self {
+        self.insert("style.defaultLang", value.into())
+    }
+
+    /// Length above which single-line block content is left as is, `0` to disable.
+    ///
+    /// Default: `0`
+    pub fn minified_threshold(&mut self, value: u32) -> &mut Self {
+        self.insert(
+            "minifiedThreshold",
+            i32::try_from(value).unwrap_or(i32::MAX).into(),
+        )
+    }
+
+    /// Merge `<style>` tags with the same attributes into the first one, unless another `<style>`
+    /// tag is between them, which would change the order of the rules.
+    ///
+    /// Default: `false`
+    pub fn merge_duplicate_styles(&mut self, value: bool) -> &mut Self {
+        self.insert("mergeDuplicateStyles", value.into())
+    }
+
+    /// Indent the children of the root element of the `<template>` tag.
+    ///
+    /// Default: `true`
+    pub fn template_body_indent(&mut self, value: bool) -> &mut Self {
+        self.insert("templateBodyIndent", value.into())
+    }
+
+    /// Extensions the content of the `<template>` tag is formatted as, in order, each formatter
+    /// receiving the output of the previous one. `{lang}` is replaced by the lang of the block.
+    ///
+    /// Default: empty, formatted as the lang of the block
+    pub fn template_pipeline(
+        &mut self,
+        stages: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.insert("template.pipeline", list_value(stages))
+    }
+
+    /// Same as [`Self::template_pipeline`] for `<script>` tags.
+    ///
+    /// Default: empty
+    pub fn script_pipeline(
+        &mut self,
+        stages: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.insert("script.pipeline", list_value(stages))
+    }
+
+    /// Same as [`Self::template_pipeline`] for `<style>` tags.
+    ///
+    /// Default: empty
+    pub fn style_pipeline(
+        &mut self,
+        stages: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.insert("style.pipeline", list_value(stages))
+    }
+
+    /// Close the last block when its end tag is missing, rather than failing to format the file.
+    ///
+    /// Default: `false`
+    pub fn auto_close_final_block(&mut self, value: bool) -> &mut Self {
+        self.insert("autoCloseFinalBlock", value.into())
+    }
+
+    /// Earlier version of the plugin whose formatting decisions are kept, only `"0.3"` is
+    /// supported.
+    ///
+    /// Default: none, the current behavior
+    pub fn compat_version(&mut self, value: &str) -> &mut Self {
+        self.insert("compatVersion", value.into())
+    }
+
+    /// Write block tag names in lowercase, such as `<Template>` as `<template>`.
+    ///
+    /// Default: `false`
+    pub fn lowercase_block_tags(&mut self, value: bool) -> &mut Self {
+        self.insert("lowercaseBlockTags", value.into())
+    }
+
+    /// Maximum depth of elements named after a block nested in its content, such as `<template>`
+    /// elements in the `<template>` block. Deeper files fail to parse.
+    ///
+    /// Default: `256`
+    pub fn max_nesting_depth(&mut self, value: u16) -> &mut Self {
+        self.insert("maxNestingDepth", i32::from(value).into())
+    }
+
+    /// Keep the byte order mark at the start of files, otherwise it is removed.
+    ///
+    /// Default: `true`
+    pub fn keep_byte_order_mark(&mut self, value: bool) -> &mut Self {
+        self.insert("keepByteOrderMark", value.into())
+    }
+
+    /// Format files containing only whitespace to a single newline instead of leaving them as is.
+    ///
+    /// Default: `false`
+    pub fn blank_file_newline(&mut self, value: bool) -> &mut Self {
+        self.insert("blankFileNewline", value.into())
+    }
+
+    /// Indent the content of the `<script>` and `<style>` tags, as `vueIndentScriptAndStyle` does in
+    /// Prettier.
+    ///
+    /// Default: `false`
+    pub fn indent_script_and_style(&mut self, value: bool) -> &mut Self {
+        self.insert("indentScriptAndStyle", value.into())
+    }
+
+    /// Indent the content of the `<template>` tag, overriding `indentTemplate`.
+    ///
+    /// Default: `indentTemplate`
+    pub fn template_indent_content(&mut self, value: bool) -> &mut Self {
+        self.insert("template.indentContent", value.into())
+    }
+
+    /// Indent the content of the `<script>` tag, overriding `indentScriptAndStyle`.
+    ///
+    /// Default: `indentScriptAndStyle`
+    pub fn script_indent_content(&mut self, value: bool) -> &mut Self {
+        self.insert("script.indentContent", value.into())
+    }
+
+    /// Indent the content of the `<style>` tag, overriding `indentScriptAndStyle`.
+    ///
+    /// Default: `indentScriptAndStyle`
+    pub fn style_indent_content(&mut self, value: bool) -> &mut Self {
+        self.insert("style.indentContent", value.into())
+    }
+
+    /// End the file with exactly one newline, otherwise the end of the file is kept as is.
+    ///
+    /// Default: `false`
+    pub fn final_newline(&mut self, value: bool) -> &mut Self {
+        self.insert("finalNewline", value.into())
+    }
+
+    /// Trim the whitespace at the end of lines outside of blocks and in block tags.
+    ///
+    /// Default: `true`, `false` with `compatVersion` `"0.3"`
+    pub fn trim_trailing_whitespace(&mut self, value: bool) -> &mut Self {
+        self.insert("trimTrailingWhitespace", value.into())
+    }
+
+    /// Remove the blank lines at the start and end of the content of every block.
+    ///
+    /// Default: `false`
+    pub fn trim_block_content(&mut self, value: bool) -> &mut Self {
+        self.insert("trimBlockContent", value.into())
+    }
+
+    /// Number of blank lines between blocks separated only by whitespace.
+    ///
+    /// Default: none, the blank lines are kept as is
+    pub fn blank_lines_between_blocks(&mut self, value: u8) -> &mut Self {
+        self.insert("blankLinesBetweenBlocks", i32::from(value).into())
+    }
+
+    /// Block names the blocks are reordered by, such as `["script", "template", "style"]`.
+    /// Comments directly above a block move with it, blocks not listed keep their order after the
+    /// listed ones.
+    ///
+    /// Default: empty, the blocks are not reordered
+    pub fn block_order(&mut self, names: impl IntoIterator<Item = impl Into<String>>) -> &mut Self {
+        self.insert("blockOrder", list_value(names))
+    }
+
+    /// Place the `<script setup>` block first or last of the `<script>` blocks, the other blocks
+    /// stay in place.
+    ///
+    /// Default: none, the `<script>` blocks are not reordered
+    pub fn script_setup_position(&mut self, value: ScriptSetupPosition) -> &mut Self {
+        self.insert("scriptSetupPosition", value.as_str().into())
+    }
+
+    /// Keep blocks written on a single line in the source on a single line, such as
+    /// `<script>export default {}</script>`, rather than expanding them.
+    ///
+    /// Default: `false`
+    pub fn keep_single_line_blocks(&mut self, value: bool) -> &mut Self {
+        self.insert("keepSingleLineBlocks", value.into())
+    }
+
+    fn insert(&mut self, name: &str, value: ConfigKeyValue) -> &mut Self {
+        self.config.insert(String::from(name), value);
+        self
+    }
+}
+
+/// Return the comma-separated list of `items`, as list options are written in the configuration.
+fn list_value(items: impl IntoIterator<Item = impl Into<String>>) -> ConfigKeyValue {
+    let items = items.into_iter().map(Into::into).collect::<Vec<String>>();
+
+    ConfigKeyValue::from_str(&items.join(","))
+}
+
+#[cfg(test)]
+mod test {
+    use dprint_core::configuration::GlobalConfiguration;
+
+    use crate::configuration::{NewLineKind, ScriptSetupPosition};
+
+    use super::ConfigurationBuilder;
+
+    #[test]
+    fn test_build() {
+        let config = ConfigurationBuilder::new()
+            .indent_template(false)
+            .use_tabs(true)
+            .indent_width(4)
+            .new_line_kind(NewLineKind::Crlf)
+            .block_order(["script", "template", "style"])
+            .script_setup_position(ScriptSetupPosition::First)
+            .script_pipeline(vec![String::from("{lang}"), String::from("md")])
+            .build();
+
+        assert!(!config.indent_template);
+        assert!(config.use_tabs);
+        assert_eq!(config.indent_width, 4);
+        assert_eq!(config.new_line_kind, Some(NewLineKind::Crlf));
+        assert_eq!(config.block_order, ["script", "template", "style"]);
+        assert_eq!(
+            config.script_setup_position,
+            Some(ScriptSetupPosition::First)
+        );
+        assert_eq!(config.script_pipeline, ["{lang}", "md"]);
+    }
+
+    #[test]
+    fn test_global_config() {
+        let config = ConfigurationBuilder::new()
+            .global_config(GlobalConfiguration {
+                line_width: Some(80),
+                use_tabs: Some(true),
+                indent_width: Some(8),
+                new_line_kind: None,
+            })
+            .build();
+
+        assert!(config.indent_template);
+        assert!(config.use_tabs);
+        assert_eq!(config.indent_width, 8);
+        assert_eq!(config.line_width, 80);
+    }
+}
@@ -0,0 +1,210 @@
+use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::ConfigKeyValue;
+use dprint_core::configuration::ConfigurationDiagnostic;
+
+use super::{NewLineKind, ScriptSetupPosition};
+
+/// A value that can be read from a [`ConfigKeyMap`].
+pub trait ConfigValue: Sized {
+    /// Description of the expected value, such as "a boolean".
+    const EXPECTED: &'static str;
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self>;
+}
+
+impl ConfigValue for bool {
+    const EXPECTED: &'static str = "a boolean";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for u8 {
+    const EXPECTED: &'static str = "an integer between 0 and 255";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::Number(value) => u8::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for u16 {
+    const EXPECTED: &'static str = "an integer between 0 and 65535";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::Number(value) => u16::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for u32 {
+    const EXPECTED: &'static str = "a non-negative integer";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::Number(value) => u32::try_from(*value).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for String {
+    const EXPECTED: &'static str = "a string";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::String(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for Vec<String> {
+    const EXPECTED: &'static str = "a comma-separated list";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::String(value) => Some(
+                value
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|item| !item.is_empty())
+                    .map(String::from)
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for NewLineKind {
+    const EXPECTED: &'static str = "one of `auto`, `lf`, `crlf` or `system`";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::String(value) => match value.as_str() {
+                "auto" => Some(NewLineKind::Auto),
+                "lf" => Some(NewLineKind::Lf),
+                "crlf" => Some(NewLineKind::Crlf),
+                "system" => Some(NewLineKind::System),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+impl ConfigValue for ScriptSetupPosition {
+    const EXPECTED: &'static str = "`first` or `last`";
+
+    fn from_value(value: &ConfigKeyValue) -> Option<Self> {
+        match value {
+            ConfigKeyValue::String(value) => match value.as_str() {
+                "first" => Some(ScriptSetupPosition::First),
+                "last" => Some(ScriptSetupPosition::Last),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+/// Remove `key` from `config` and parse it, reporting the key, the expected type and the received
+/// value when it cannot be parsed.
+pub fn get_value<T: ConfigValue>(
+    config: &mut ConfigKeyMap,
+    key: &str,
+    default_value: T,
+    diagnostics: &mut Vec<ConfigurationDiagnostic>,
+) -> T {
+    get_nullable_value(config, key, diagnostics).unwrap_or(default_value)
+}
+
+/// See [`get_value`].
+pub fn get_nullable_value<T: ConfigValue>(
+    config: &mut ConfigKeyMap,
+    key: &str,
+    diagnostics: &mut Vec<ConfigurationDiagnostic>,
+) -> Option<T> {
+    let value = config.remove(key)?;
+
+    let parsed = T::from_value(&value);
+
+    if parsed.is_none() {
+        diagnostics.push(ConfigurationDiagnostic {
+            property_name: String::from(key),
+            message: format!(
+                "Expected {} for `{key}`, received {}.",
+                T::EXPECTED,
+                describe(&value)
+            ),
+        });
+    }
+
+    parsed
+}
+
+fn describe(value: &ConfigKeyValue) -> String {
+    match value {
+        ConfigKeyValue::String(value) => format!("the string {value:?}"),
+        ConfigKeyValue::Number(value) => format!("the number `{value}`"),
+        ConfigKeyValue::Bool(value) => format!("the boolean `{value}`"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use dprint_core::configuration::ConfigKeyValue;
+
+    use super::get_value;
+
+    #[test]
+    fn test_get_value() {
+        let mut config = HashMap::from([
+            (
+                String::from("indentTemplate"),
+                ConfigKeyValue::from_str("no"),
+            ),
+            (String::from("indentWidth"), ConfigKeyValue::from_i32(256)),
+            (String::from("useTabs"), ConfigKeyValue::from_bool(true)),
+        ]);
+
+        let mut diagnostics = Vec::new();
+
+        assert!(get_value(
+            &mut config,
+            "indentTemplate",
+            true,
+            &mut diagnostics
+        ));
+        assert_eq!(
+            get_value(&mut config, "indentWidth", 2u8, &mut diagnostics),
+            2
+        );
+        assert!(get_value(&mut config, "useTabs", false, &mut diagnostics));
+
+        assert!(config.is_empty());
+
+        assert_eq!(diagnostics.len(), 2);
+        assert_eq!(diagnostics[0].property_name, "indentTemplate");
+        assert_eq!(
+            diagnostics[0].message,
+            "Expected a boolean for `indentTemplate`, received the string \"no\"."
+        );
+        assert_eq!(diagnostics[1].property_name, "indentWidth");
+        assert_eq!(
+            diagnostics[1].message,
+            "Expected an integer between 0 and 255 for `indentWidth`, received the number `256`."
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::fmt;
+
+use crate::parser::{offset_of, parse_sections, Block, Section};
+
+/// A lossless concrete syntax tree of a Vue SFC, where every byte of the source belongs to a node.
+///
+/// Writing the tree back with [`fmt::Display`] reproduces the source exactly, including after
+/// nodes are moved around, such as to reorder blocks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cst<'a> {
+    pub nodes: Vec<CstNode<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CstNode<'a> {
+    /// Text before, after or between blocks, such as whitespace and comments.
+    Trivia(&'a str),
+    Block(CstBlock<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CstBlock<'a> {
+    pub start_tag: Vec<CstToken<'a>>,
+    /// The `\r\n`, `\n` or `\r` line break following the start tag, which is not part of the
+    /// content, or nothing.
+    pub leading_newline: &'a str,
+    pub content: &'a str,
+    /// Empty for self-closing blocks.
+    pub end_tag: Vec<CstToken<'a>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CstToken<'a> {
+    pub kind: CstTokenKind,
+    pub text: &'a str,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CstTokenKind {
+    /// `<` or `</`.
+    Open,
+    Name,
+    /// A whole attribute such as `lang="ts"`.
+    Attribute,
+    Whitespace,
+    /// `>` or `/>`.
+    Close,
+    /// Anything else, such as the unparsed rest of a malformed start tag.
+    Other,
+}
+
+impl<'a> Cst<'a> {
+    /// Parse a Vue SFC without failing, keeping unterminated blocks and unparsable input as trivia.
+    pub fn parse(source: &'a str) -> Self {
+        let sections = parse_sections(source).unwrap_or_else(|_| vec![Section::Raw(source)]);
+
+        Self {
+            nodes: sections
+                .into_iter()
+                .map(|section| match section {
+                    Section::Raw(text) => CstNode::Trivia(text),
+                    Section::Block(block) => CstNode::Block(CstBlock::new(source, &block)),
+                })
+                .collect(),
+        }
+    }
+
+    /// Iterate over the blocks, in tree order.
+    pub fn blocks(&self) -> impl Iterator<Item = &CstBlock<'a>> {
+        self.nodes.iter().filter_map(|node| match node {
+            CstNode::Block(block) => Some(block),
+            CstNode::Trivia(_) => None,
+        })
+    }
+}
+
+impl<'a> CstBlock<'a> {
+    fn new(source: &'a str, block: &Block<'a>) -> Self {
+        let raw_start_tag = block.raw_start_tag;
+        let name_end = 1 + block.start_tag.name.len();
+
+        let mut start_tag = vec![
+            CstToken::new(CstTokenKind::Open, &raw_start_tag[..1]),
+            CstToken::new(CstTokenKind::Name, &raw_start_tag[1..name_end]),
+        ];
+
+        let mut offset = name_end;
+
+        for attribute in &block.start_tag.attributes {
+            let start = attribute.span.start - block.start_tag.span.start;
+            let end = attribute.span.end - block.start_tag.span.start;
+
+            push_gap(&mut start_tag, &raw_start_tag[offset..start]);
+            start_tag.push(CstToken::new(
+                CstTokenKind::Attribute,
+                &raw_start_tag[start..end],
+            ));
+
+            offset = end;
+        }
+
+        push_tail(&mut start_tag, &raw_start_tag[offset..]);
+
+        let mut end_tag = Vec::new();
+
+        if let Some(rest) = block.raw_end_tag.strip_prefix("</") {
+            let name_end = 2 + rest
+                .find(|char: char| char.is_ascii_whitespace() || char == '>')
+                .unwrap_or(rest.len());
+
+            end_tag.push(CstToken::new(CstTokenKind::Open, &block.raw_end_tag[..2]));
+            end_tag.push(CstToken::new(
+                CstTokenKind::Name,
+                &block.raw_end_tag[2..name_end],
+            ));
+            push_tail(&mut end_tag, &block.raw_end_tag[name_end..]);
+        }
+
+        let start_tag_end = offset_of(source, raw_start_tag) + raw_start_tag.len();
+
+        Self {
+            start_tag,
+            leading_newline: &source[start_tag_end..offset_of(source, block.content)],
+            content: block.content,
+            end_tag,
+        }
+    }
+
+    /// The name of the block, as written in the start tag.
+    pub fn name(&self) -> &'a str {
+        self.start_tag[1].text
+    }
+}
+
+impl<'a> CstToken<'a> {
+    const fn new(kind: CstTokenKind, text: &'a str) -> Self {
+        Self { kind, text }
+    }
+}
+
+/// Push the text between two tokens of a tag, if any.
+fn push_gap<'a>(tokens: &mut Vec<CstToken<'a>>, text: &'a str) {
+    if text.is_empty() {
+        return;
+    }
+
+    let kind = if text.trim().is_empty() {
+        CstTokenKind::Whitespace
+    } else {
+        CstTokenKind::Other
+    };
+
+    tokens.push(CstToken::new(kind, text));
+}
+
+/// Push the text after the last attribute of a tag, ending with `>` or `/>`.
+fn push_tail<'a>(tokens: &mut Vec<CstToken<'a>>, text: &'a str) {
+    let close = if text.ends_with("/>") {
+        2
+    } else {
+        usize::from(text.ends_with('>'))
+    };
+    let (gap, close) = text.split_at(text.len() - close);
+
+    push_gap(tokens, gap);
+
+    if !close.is_empty() {
+        tokens.push(CstToken::new(CstTokenKind::Close, close));
+    }
+}
+
+impl fmt::Display for Cst<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for node in &self.nodes {
+            match node {
+                CstNode::Trivia(text) => f.write_str(text)?,
+                CstNode::Block(block) => write!(f, "{block}")?,
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for CstBlock<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.start_tag {
+            f.write_str(token.text)?;
+        }
+
+        f.write_str(self.leading_newline)?;
+        f.write_str(self.content)?;
+
+        for token in &self.end_tag {
+            f.write_str(token.text)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Cst, CstNode, CstToken, CstTokenKind};
+
+    #[test]
+    fn test_cst_round_trip() {
+        let cases = [
+            "",
+            "<!-- a -->\n<template>\n<div></div>\n</template>\n\n<script setup lang=\"ts\" >let a;</script >\n",
+            "<style src='./a.css'/>\n<style  scoped\tmodule=\"b\"   />",
+            "<script lang=\"ts>\nlet a;\n</script>\n",
+            "<template>\n<div>\n",
+            "<i18n lang=json>{}</I18N>",
+        ];
+
+        for source in cases {
+            assert_eq!(Cst::parse(source).to_string(), source, "{source:?}");
+        }
+    }
+
+    #[test]
+    fn test_cst_tokens() {
+        let cst = Cst::parse("<script  setup lang=\"ts\"/>\n<style>\na {}\n</style >");
+
+        let CstNode::Block(block) = &cst.nodes[0] else {
+            panic!("expected a block");
+        };
+
+        assert_eq!(
+            block.start_tag,
+            [
+                CstToken::new(CstTokenKind::Open, "<"),
+                CstToken::new(CstTokenKind::Name, "script"),
+                CstToken::new(CstTokenKind::Whitespace, "  "),
+                CstToken::new(CstTokenKind::Attribute, "setup"),
+                CstToken::new(CstTokenKind::Whitespace, " "),
+                CstToken::new(CstTokenKind::Attribute, "lang=\"ts\""),
+                CstToken::new(CstTokenKind::Close, "/>"),
+            ]
+        );
+        assert!(block.end_tag.is_empty());
+
+        let blocks = cst.blocks().collect::<Vec<_>>();
+
+        assert_eq!(blocks[1].name(), "style");
+        assert_eq!(blocks[1].leading_newline, "\n");
+        assert_eq!(blocks[1].content, "a {}\n");
+        assert_eq!(
+            blocks[1].end_tag,
+            [
+                CstToken::new(CstTokenKind::Open, "</"),
+                CstToken::new(CstTokenKind::Name, "style"),
+                CstToken::new(CstTokenKind::Whitespace, " "),
+                CstToken::new(CstTokenKind::Close, ">"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cst_reorder() {
+        let mut cst = Cst::parse("<template></template>\n<script></script>\n");
+
+        cst.nodes.swap(0, 2);
+
+        assert_eq!(
+            cst.to_string(),
+            "<script></script>\n<template></template>\n"
+        );
+    }
+}
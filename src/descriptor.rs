@@ -0,0 +1,246 @@
+#[cfg(feature = "plugin")]
+use anyhow::Result;
+
+#[cfg(feature = "plugin")]
+use crate::assemble::{assemble, Placement, Unit};
+#[cfg(feature = "plugin")]
+use crate::configuration::Configuration;
+#[cfg(feature = "plugin")]
+use crate::format::format_block_content;
+use crate::parser::{
+    parse_file_with, parse_sections, Block, ParseError, ParseOptions, Section, Span,
+};
+use crate::trivia::Trivia;
+use crate::validate::{validate_sections, Diagnostic};
+
+/// A parsed Vue SFC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SfcDescriptor<'a> {
+    /// The blocks and the text around them, in source order.
+    pub sections: Vec<Section<'a>>,
+}
+
+/// Parse a Vue SFC into its blocks, see [`SfcDescriptor::parse`].
+pub fn parse_sfc(source: &str) -> Result<SfcDescriptor<'_>, ParseError> {
+    SfcDescriptor::parse(source)
+}
+
+impl<'a> SfcDescriptor<'a> {
+    pub fn parse(source: &'a str) -> Result<Self, ParseError> {
+        Self::parse_with(source, &ParseOptions::default())
+    }
+
+    /// See [`SfcDescriptor::parse`].
+    pub fn parse_with(source: &'a str, options: &ParseOptions) -> Result<Self, ParseError> {
+        Ok(Self {
+            sections: parse_file_with(source, options)?,
+        })
+    }
+
+    /// Parse a Vue SFC without failing, keeping malformed regions such as unterminated blocks as
+    /// raw text and reporting them as diagnostics.
+    pub fn parse_lenient(source: &'a str) -> (Self, Vec<Diagnostic>) {
+        let sections = parse_sections(source).unwrap_or_else(|_| vec![Section::Raw(source)]);
+        let diagnostics = validate_sections(source, &sections);
+
+        (Self { sections }, diagnostics)
+    }
+
+    /// Iterate over the blocks, in source order.
+    pub fn blocks(&self) -> impl Iterator<Item = &Block<'a>> {
+        self.sections.iter().filter_map(|section| match section {
+            Section::Block(block) => Some(block),
+            Section::Raw(_) => None,
+        })
+    }
+
+    /// Iterate over the text before, after and between the blocks, in source order.
+    pub fn trivia(&self) -> impl Iterator<Item = Trivia<'a>> + '_ {
+        let mut offset = 0;
+
+        self.sections
+            .iter()
+            .filter_map(move |section| match section {
+                Section::Raw(text) => {
+                    let span = Span::new(offset, offset + text.len());
+                    offset = span.end;
+
+                    Some(Trivia { text, span })
+                }
+                Section::Block(block) => {
+                    offset = block.span.end;
+
+                    None
+                }
+            })
+    }
+
+    /// The first `<template>` block, if any.
+    pub fn template(&self) -> Option<&Block<'a>> {
+        self.blocks_named("template").next()
+    }
+
+    /// The `<script>` blocks, including `<script setup>`.
+    pub fn scripts(&self) -> impl Iterator<Item = &Block<'a>> {
+        self.blocks_named("script")
+    }
+
+    /// The `<style>` blocks.
+    pub fn styles(&self) -> impl Iterator<Item = &Block<'a>> {
+        self.blocks_named("style")
+    }
+
+    /// The blocks other than `<template>`, `<script>` and `<style>`, such as `<i18n>`.
+    pub fn custom_blocks(&self) -> impl Iterator<Item = &Block<'a>> {
+        self.blocks().filter(|block| {
+            !["template", "script", "style"]
+                .iter()
+                .any(|name| block.start_tag.name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    fn blocks_named<'b>(&'b self, name: &'b str) -> impl Iterator<Item = &'b Block<'a>> {
+        self.blocks()
+            .filter(move |block| block.start_tag.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Serialize the file back, applying this plugin's own normalizations but leaving the block
+    /// contents to be formatted by no other plugin.
+    #[cfg(feature = "plugin")]
+    pub fn emit(&self, config: &Configuration) -> String {
+        self.emit_with(config, |block| {
+            Ok(Unit::Block {
+                block: block.clone(),
+                content: format_block_content(block, config, &mut |_, text, _| Ok(text))?,
+            })
+        })
+        .expect("formatting without host should not fail")
+        .0
+    }
+
+    /// Serialize the file back, producing the unit of each block with `format_block` and returning
+    /// where each block ended up.
+    #[cfg(feature = "plugin")]
+    pub(crate) fn emit_with(
+        &self,
+        config: &Configuration,
+        mut format_block: impl FnMut(&Block<'a>) -> Result<Unit<'a>>,
+    ) -> Result<(String, Vec<Placement>)> {
+        let mut units = Vec::with_capacity(self.sections.len());
+
+        for section in &self.sections {
+            units.push(match section {
+                Section::Raw(text) => Unit::Raw(text),
+                Section::Block(block) => format_block(block)?,
+            });
+        }
+
+        Ok(assemble(units, config))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    #[cfg(feature = "plugin")]
+    use crate::configuration::Configuration;
+
+    use crate::parser::Span;
+    use crate::validate::Diagnostic;
+
+    use super::{parse_sfc, SfcDescriptor};
+
+    #[test]
+    #[cfg(feature = "plugin")]
+    fn test_emit() {
+        let config = Configuration {
+            indent_template: true,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let descriptor = SfcDescriptor::parse(
+            "<template><div></div>\n\n\n<p></p></template>\n<script>\nexport default {}\n\n</script>\n",
+        )
+        .unwrap();
+
+        assert_eq!(
+            descriptor.emit(&config),
+            "<template>\n  <div></div>\n\n  <p></p>\n</template>\n<script>\nexport default {}\n</script>\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        let source = "<template>\n<div>\n<script>\nlet a;   \n</script>\n";
+
+        assert!(SfcDescriptor::parse(source).is_err());
+
+        let (descriptor, diagnostics) = SfcDescriptor::parse_lenient(source);
+
+        assert_eq!(descriptor.blocks().count(), 1);
+        assert_eq!(
+            diagnostics,
+            [Diagnostic {
+                message: String::from("Unterminated `<template>` block."),
+                range: 0..16,
+            }]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "plugin")]
+    fn test_emit_lenient() {
+        let (descriptor, _) =
+            SfcDescriptor::parse_lenient("<template>\n<div>\n<script>\nlet a;   \n</script>\n");
+
+        assert_eq!(
+            descriptor.emit(&Configuration::default()),
+            "<template>\n<div>\n<script>\nlet a;\n</script>\n"
+        );
+    }
+
+    #[test]
+    fn test_trivia() {
+        let descriptor =
+            parse_sfc("<!-- a -->\n<template></template>\n\n<script></script><style></style>\n")
+                .unwrap();
+
+        assert_eq!(
+            descriptor
+                .trivia()
+                .map(|trivia| (trivia.text, trivia.span))
+                .collect::<Vec<_>>(),
+            [
+                ("<!-- a -->\n", Span::new(0, 11)),
+                ("\n\n", Span::new(32, 34)),
+                ("\n", Span::new(66, 67)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_sfc() {
+        let descriptor = parse_sfc(concat!(
+            "<script>\nexport default {}\n</script>\n<script setup>\nlet a;\n</script>\n",
+            "<TEMPLATE></TEMPLATE>\n<style scoped></style>\n<i18n></i18n>\n",
+        ))
+        .unwrap();
+
+        assert_eq!(descriptor.template().unwrap().raw_start_tag, "<TEMPLATE>");
+        assert_eq!(descriptor.scripts().count(), 2);
+        assert!(descriptor.scripts().any(|block| block.start_tag.setup()));
+        assert_eq!(descriptor.styles().count(), 1);
+        assert_eq!(
+            descriptor
+                .custom_blocks()
+                .map(|block| block.start_tag.name)
+                .collect::<Vec<_>>(),
+            ["i18n"]
+        );
+
+        assert_eq!(
+            parse_sfc("<script>\n").unwrap_err().to_string(),
+            "Unterminated `<script>` block starting at line 1, column 1."
+        );
+    }
+}
@@ -1,103 +1,784 @@
-use std::collections::HashMap;
-use std::iter::repeat;
+use std::borrow::Cow;
 use std::path::Path;
 use std::path::PathBuf;
 
 use anyhow::Result;
-use dprint_core::configuration::ConfigKeyMap;
+use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
 
+use crate::assemble::Unit;
 use crate::configuration::Configuration;
-use crate::parser::parse_file;
+use crate::descriptor::SfcDescriptor;
+use crate::indentation::{detect_indentation, detect_indentation_except, Indentation};
+use crate::lang::script_type_lang;
+use crate::parser::close_final_block;
+use crate::parser::line_break_ends;
+use crate::parser::parse_well_formed_start_tag;
 use crate::parser::Block;
-use crate::parser::Section;
 use crate::parser::StartTag;
+use crate::parser::{script_literals, style_literals};
+use crate::report::{BlockReport, BlockTrace, Normalization, SkipReason};
 
-fn default_lang(block: &str) -> Option<&'static str> {
-    match block {
-        "template" => Some("html"),
-        "script" => Some("js"),
-        "style" => Some("css"),
+const BOM: char = '\u{feff}';
+
+fn default_lang<'a>(block: &str, config: &'a Configuration) -> Option<&'a str> {
+    match block.to_ascii_lowercase().as_str() {
+        "template" => Some(&config.template_default_lang),
+        "script" => Some(&config.script_default_lang),
+        "style" => Some(&config.style_default_lang),
         _ => None,
     }
 }
 
+/// Return the stages of `{block}.pipeline`. The content is passed to the host as
+/// `file.vue.{stage}`, which dprint routes by its final extension only, so a stage can only pick a
+/// formatter by extension.
+fn pipeline<'a>(block: &str, config: &'a Configuration) -> &'a [String] {
+    match block.to_ascii_lowercase().as_str() {
+        "template" => &config.template_pipeline,
+        "script" => &config.script_pipeline,
+        "style" => &config.style_pipeline,
+        _ => &[],
+    }
+}
+
+/// Whether the content of blocks named `name` is indented, see `indentTemplate`,
+/// `indentScriptAndStyle` and `{block}.indentContent`.
+pub(crate) fn indents_content(name: &str, config: &Configuration) -> bool {
+    match name.to_ascii_lowercase().as_str() {
+        "template" => config
+            .template_indent_content
+            .unwrap_or(config.indent_template),
+        "script" => config
+            .script_indent_content
+            .unwrap_or(config.indent_script_and_style),
+        "style" => config
+            .style_indent_content
+            .unwrap_or(config.indent_script_and_style),
+        _ => false,
+    }
+}
+
+/// Return the lang the block content is formatted as, if any.
+pub(crate) fn resolve_lang<'a>(
+    block: &Block<'a>,
+    config: &'a Configuration,
+) -> Option<Cow<'a, str>> {
+    if let Some(lang) = block.start_tag.decoded_lang() {
+        return Some(lang);
+    }
+
+    if block.start_tag.name.eq_ignore_ascii_case("script") {
+        if let Some(script_type) = block.start_tag.script_type() {
+            return script_type_lang(&script_type).map(Cow::Borrowed);
+        }
+    }
+
+    default_lang(block.start_tag.name, config).map(Cow::Borrowed)
+}
+
+/// Return why the block content should not be formatted, if it should not.
+pub(crate) fn skip_reason(block: &Block, config: &Configuration) -> Option<SkipReason> {
+    if block.start_tag.self_closing {
+        return Some(SkipReason::SelfClosing);
+    }
+
+    if block.start_tag.src.is_some() && block.content.trim().is_empty() {
+        return Some(SkipReason::External);
+    }
+
+    if resolve_lang(block, config).is_none() {
+        return Some(SkipReason::UnknownLang);
+    }
+
+    if config.is_v0_3() && parse_well_formed_start_tag(block.raw_start_tag).is_err() {
+        return Some(SkipReason::MalformedStartTag);
+    }
+
+    if config.minified_threshold > 0 {
+        let content = block.content.trim();
+
+        if content.len() > config.minified_threshold as usize && !content.contains('\n') {
+            return Some(SkipReason::Minified);
+        }
+    }
+
+    None
+}
+
+/// Whether the file is a snippet rather than a file on disk, the markdown plugin formats code
+/// blocks as `file.{ext}`.
+pub(crate) fn is_snippet(path: &Path) -> bool {
+    path == Path::new("file.vue")
+}
+
+/// Format a Vue SFC.
 pub fn format(
     _path: &Path,
     content: &str,
     config: &Configuration,
     mut format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
 ) -> Result<String> {
-    let mut buffer = String::new();
-
-    let sections = parse_file(content)?;
-
-    for section in sections {
-        match section {
-            Section::Raw(text) => buffer.push_str(text),
-            Section::Block(Block {
-                start_tag: StartTag { name, lang },
-                content,
-                raw_start_tag,
-                raw_end_tag,
-            }) => {
-                buffer.push_str(raw_start_tag);
-                buffer.push('\n');
-
-                let lang = lang.or_else(|| default_lang(name));
-
-                if let Some(lang) = lang {
-                    let file_path = PathBuf::from(format!("file.vue.{lang}"));
-
-                    let pretty = {
-                        let pretty =
-                            format_with_host(&file_path, String::from(content), &HashMap::new())?;
-
-                        if name.eq_ignore_ascii_case("template") && config.indent_template {
-                            let indent_width = usize::from(config.indent_width);
-
-                            let mut buffer = String::with_capacity(
-                                pretty.len() + pretty.lines().count() * indent_width,
-                            );
-
-                            for line in pretty.trim_start().lines() {
-                                buffer.extend(
-                                    repeat(if config.use_tabs { '\t' } else { ' ' })
-                                        .take(indent_width),
-                                );
-                                buffer.push_str(line);
-                                buffer.push('\n');
-                            }
-
-                            buffer
-                        } else {
-                            pretty
-                        }
-                    };
-
-                    buffer.push_str(pretty.trim_end());
-                } else {
-                    buffer.push_str(content);
+    let (pretty, _) = format_file(content, config, false, |block, trace| {
+        format_block_content_traced(block, config, &mut format_with_host, trace)
+    })?;
+
+    Ok(pretty)
+}
+
+/// Format a whole file with `format_block`, applying the file level normalizations, and report
+/// where each block ended up. [`format`], [`crate::format_with_report`] and the plugin all go
+/// through here.
+///
+/// Snippets are formatted leniently: they are returned as is if they cannot be parsed, and blocks
+/// that cannot be formatted are left untouched. Their end is left to the host, such as the
+/// markdown plugin for code blocks.
+pub(crate) fn format_file(
+    content: &str,
+    config: &Configuration,
+    snippet: bool,
+    mut format_block: impl FnMut(&Block, &mut BlockTrace) -> Result<String>,
+) -> Result<(String, Vec<BlockReport>)> {
+    let (bom, text) = match content.strip_prefix(BOM) {
+        Some(text) => (BOM.len_utf8(), text),
+        None => (0, content),
+    };
+
+    let line_break = config.new_line_kind.map(|kind| kind.resolve(text));
+
+    let (mut pretty, mut blocks) = match format_blank(text, config) {
+        Some(pretty) => (pretty, Vec::new()),
+        None => format_blocks(text, config, snippet, &mut format_block)?,
+    };
+
+    if !snippet {
+        end_with_newline(&mut pretty, config);
+    }
+
+    if let Some(line_break) = line_break {
+        if let Cow::Owned(normalized) = normalize_line_breaks(&pretty, line_break) {
+            // Blocks start with `<` and end with `>`, no line break straddles their bounds.
+            let offset = |offset: usize| normalize_line_breaks(&pretty[..offset], line_break).len();
+
+            for block in &mut blocks {
+                block.output = offset(block.output.start)..offset(block.output.end);
+            }
+
+            pretty = normalized;
+        }
+    }
+
+    if bom > 0 && config.keep_byte_order_mark {
+        pretty.insert(0, BOM);
+
+        for block in &mut blocks {
+            block.output = (block.output.start + bom)..(block.output.end + bom);
+        }
+    }
+
+    for block in &mut blocks {
+        // The input of an auto-closed block ends past the end of the file.
+        block.input = (block.input.start + bom)..(block.input.end + bom).min(content.len());
+        block.changed = pretty[block.output.clone()] != content[block.input.clone()];
+    }
+
+    Ok((pretty, blocks))
+}
+
+/// Format the blocks of `content`, a file without byte order mark, and concatenate them with the
+/// text around them, see [`format_file`].
+fn format_blocks(
+    content: &str,
+    config: &Configuration,
+    snippet: bool,
+    format_block: &mut impl FnMut(&Block, &mut BlockTrace) -> Result<String>,
+) -> Result<(String, Vec<BlockReport>)> {
+    let closed = auto_close(content, config);
+    let content = closed.as_deref().unwrap_or(content);
+
+    let descriptor = match SfcDescriptor::parse_with(content, &config.parse_options()) {
+        Ok(descriptor) => descriptor,
+        Err(_) if snippet => return Ok((String::from(content), Vec::new())),
+        Err(err) => return Err(err.into()),
+    };
+
+    let mut traces = Vec::new();
+
+    let (pretty, placements) = descriptor.emit_with(config, |block| {
+        let mut trace = BlockTrace::default();
+
+        match format_block(block, &mut trace) {
+            Ok(pretty) => {
+                traces.push((block.clone(), trace));
+
+                Ok(Unit::Block {
+                    block: block.clone(),
+                    content: pretty,
+                })
+            }
+            Err(_) if snippet => Ok(Unit::Raw(&content[block.range(content)])),
+            Err(err) => Err(err),
+        }
+    })?;
+
+    let blocks = traces
+        .into_iter()
+        .map(|(block, mut trace)| {
+            let placement = placements
+                .iter()
+                .find(|placement| placement.source_start == block.span.start)
+                .expect("every formatted block is placed");
+
+            trace
+                .normalizations
+                .extend_from_slice(&placement.normalizations);
+
+            BlockReport {
+                name: block.start_tag.name.to_string(),
+                lang: resolve_lang(&block, config).map(Cow::into_owned),
+                changed: false,
+                skipped: skip_reason(&block, config),
+                formatters: trace.formatters,
+                input: block.range(content),
+                output: placement.output.clone(),
+                normalizations: trace.normalizations,
+            }
+        })
+        .collect();
+
+    Ok((pretty, blocks))
+}
+
+/// Format `content` with `f`, ending the result with a single newline if `finalNewline` is
+/// enabled and converting every line break to `newLineKind` if set.
+///
+/// The end of snippets is left to the host, such as the markdown plugin for code blocks.
+pub(crate) fn with_file_normalizations(
+    content: &str,
+    config: &Configuration,
+    snippet: bool,
+    f: impl FnOnce(&str) -> Result<String>,
+) -> Result<String> {
+    let line_break = config.new_line_kind.map(|kind| kind.resolve(content));
+    let mut pretty = f(content)?;
+
+    if !snippet {
+        end_with_newline(&mut pretty, config);
+    }
+
+    Ok(
+        match line_break.map(|line_break| normalize_line_breaks(&pretty, line_break)) {
+            Some(Cow::Owned(normalized)) => normalized,
+            _ => pretty,
+        },
+    )
+}
+
+/// End `pretty` with a single newline if it is not blank and `finalNewline` is enabled.
+fn end_with_newline(pretty: &mut String, config: &Configuration) {
+    if config.final_newline && !pretty.trim().is_empty() {
+        pretty.truncate(pretty.trim_end().len());
+        pretty.push('\n');
+    }
+}
+
+/// Replace every `\r\n`, `\n` or `\r` line break of `text` with `line_break`.
+fn normalize_line_breaks<'a>(text: &'a str, line_break: &str) -> Cow<'a, str> {
+    let mut buffer: Option<String> = None;
+    let mut start = 0;
+
+    for end in line_break_ends(text) {
+        let line = text[start..end].trim_end_matches(['\r', '\n']);
+
+        if buffer.is_none() && text[start + line.len()..end] == *line_break {
+            start = end;
+            continue;
+        }
+
+        let buffer = buffer.get_or_insert_with(|| {
+            let mut buffer = String::with_capacity(text.len());
+            buffer.push_str(&text[..start]);
+            buffer
+        });
+
+        buffer.push_str(line);
+        buffer.push_str(line_break);
+        start = end;
+    }
+
+    match buffer {
+        Some(mut buffer) => {
+            buffer.push_str(&text[start..]);
+            Cow::Owned(buffer)
+        }
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Return the formatted text of `content` if it contains only whitespace, see
+/// `blankFileNewline`.
+pub(crate) fn format_blank(content: &str, config: &Configuration) -> Option<String> {
+    if !content.trim().is_empty() {
+        return None;
+    }
+
+    Some(String::from(if config.blank_file_newline {
+        "\n"
+    } else {
+        content
+    }))
+}
+
+/// Return `content` with its last block closed if it is missing its end tag and
+/// `autoCloseFinalBlock` is enabled.
+pub(crate) fn auto_close(content: &str, config: &Configuration) -> Option<String> {
+    if config.auto_close_final_block {
+        close_final_block(content)
+    } else {
+        None
+    }
+}
+
+/// Format the content of a block, the text between the newline following its start tag and its
+/// end tag.
+pub(crate) fn format_block_content(
+    block: &Block,
+    config: &Configuration,
+    format_with_host: &mut impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
+) -> Result<String> {
+    format_block_content_traced(block, config, format_with_host, &mut BlockTrace::default())
+}
+
+/// Same as [`format_block_content`], recording the stages the content went through and the
+/// normalizations which changed it.
+pub(crate) fn format_block_content_traced(
+    block: &Block,
+    config: &Configuration,
+    format_with_host: &mut impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
+    trace: &mut BlockTrace,
+) -> Result<String> {
+    let Block {
+        start_tag: StartTag { name, .. },
+        content,
+        ..
+    } = *block;
+
+    let lang = resolve_lang(block, config).filter(|_| skip_reason(block, config).is_none());
+
+    let mut buffer = match lang {
+        Some(lang) => {
+            // Child plugins format the content as a file of its own, at the top level. The lines
+            // starting inside multi-line strings and template literals are left as is.
+            let literals = literal_lines(name, &lang, content);
+            let base = detect_indentation_except(content, &literals);
+
+            let mut pretty = if base.width > 0 && !config.is_v0_3() {
+                trace.normalizations.push(Normalization::Dedented);
+                base.dedent_except(content, &literals)
+            } else {
+                String::from(content)
+            };
+
+            let stages = match pipeline(name, config) {
+                [] => vec![lang.to_string()],
+                stages => stages
+                    .iter()
+                    .map(|stage| stage.replace("{lang}", &lang))
+                    .collect(),
+            };
+
+            let indented = indents_content(name, config);
+
+            let mut override_config = ConfigKeyMap::new();
+
+            if indented && !config.is_v0_3() {
+                // Child plugins indent with tabs or spaces as this plugin does, so that the lines
+                // of indented content never mix them.
+                override_config.insert(
+                    String::from("useTabs"),
+                    ConfigKeyValue::from_bool(config.use_tabs),
+                );
+
+                // Leave room for the indentation added once the child plugins are done.
+                let line_width = config
+                    .line_width
+                    .saturating_sub(u32::from(config.indent_width));
+
+                override_config.insert(
+                    String::from("lineWidth"),
+                    i32::try_from(line_width).unwrap_or(i32::MAX).into(),
+                );
+            }
+
+            for stage in stages {
+                let file_path = PathBuf::from(format!("file.vue.{stage}"));
+                pretty = format_with_host(&file_path, pretty, &override_config)?;
+                trace.formatters.push(stage);
+            }
+
+            if name.eq_ignore_ascii_case("template") {
+                let collapsed =
+                    collapse_blank_lines(&pretty, usize::from(config.template_max_blank_lines));
+
+                if collapsed.len() != pretty.len() {
+                    trace
+                        .normalizations
+                        .push(Normalization::CollapsedBlankLines);
+                    pretty = collapsed;
                 }
 
-                match buffer.chars().last() {
-                    Some('\n') => {}
-                    _ => buffer.push('\n'),
+                if !config.template_body_indent {
+                    if let Some(dedented) = dedent_root_children(&pretty) {
+                        trace
+                            .normalizations
+                            .push(Normalization::DedentedRootChildren);
+                        pretty = dedented;
+                    }
                 }
+            }
 
-                buffer.push_str(raw_end_tag);
+            if indented {
+                trace.normalizations.push(Normalization::Reindented);
+
+                pretty = if config.is_v0_3() {
+                    indent_v0_3(&pretty, config.indentation())
+                } else {
+                    let literals = literal_lines(name, &lang, &pretty);
+                    config.indentation().indent_except(&pretty, &literals)
+                };
             }
+
+            pretty.truncate(pretty.trim_end().len());
+            pretty
+        }
+        None => String::from(content),
+    };
+
+    if config.trim_block_content {
+        if let Some(trimmed) = trim_blank_lines(&buffer) {
+            trace.normalizations.push(Normalization::TrimmedBlankLines);
+            buffer = String::from(trimmed);
         }
     }
 
+    if !buffer.is_empty() && !buffer.ends_with('\n') {
+        buffer.push('\n');
+    }
+
     Ok(buffer)
 }
+
+/// Return the sorted offsets of the lines of the content of a `name` block which start inside a
+/// string, template literal or other text whose indentation is part of its value.
+///
+/// Only the content of `<script>` blocks in a JavaScript or TypeScript lang, of `<style>` blocks
+/// and of HTML `<template>` blocks is scanned.
+fn literal_lines(name: &str, lang: &str, content: &str) -> Vec<usize> {
+    let lang = lang.to_ascii_lowercase();
+
+    let literals = if name.eq_ignore_ascii_case("script") {
+        match lang.as_str() {
+            "js" | "jsx" | "mjs" | "cjs" | "ts" | "tsx" | "mts" | "cts" => script_literals(content),
+            _ => return Vec::new(),
+        }
+    } else if name.eq_ignore_ascii_case("style") {
+        style_literals(content, matches!(lang.as_str(), "scss" | "sass" | "less"))
+    } else if name.eq_ignore_ascii_case("template") && lang == "html" {
+        let mut region = TemplateRegion::Text;
+        let mut offsets = Vec::new();
+        let mut offset = 0;
+
+        for line in content.split_inclusive('\n') {
+            if region.is_literal() {
+                offsets.push(offset);
+            }
+
+            region = region.advance(line);
+            offset += line.len();
+        }
+
+        return offsets;
+    } else {
+        return Vec::new();
+    };
+
+    literals
+        .into_iter()
+        .flat_map(|literal| {
+            content[literal.clone()]
+                .match_indices('\n')
+                .map(move |(index, _)| literal.start + index + 1)
+                .filter(move |&offset| offset < literal.end)
+        })
+        .collect()
+}
+
+/// Prefix every line of `text` with `indentation`, blank lines included, as version 0.3 did.
+fn indent_v0_3(text: &str, indentation: Indentation) -> String {
+    text.trim_start()
+        .lines()
+        .map(|line| format!("{indentation}{line}\n"))
+        .collect()
+}
+
+/// Return `text` without its leading and trailing blank lines, if it has any.
+fn trim_blank_lines(text: &str) -> Option<&str> {
+    let end = text.trim_end().len();
+    let start = text[..end]
+        .find(|char: char| !char.is_whitespace())
+        .and_then(|first| line_break_ends(&text[..first]).last())
+        .unwrap_or(0);
+
+    // The line break ending the last line is not a blank line.
+    let blank_end = !matches!(&text[end..], "" | "\n" | "\r\n" | "\r");
+
+    (start > 0 || blank_end).then_some(&text[start..end])
+}
+
+/// Collapse runs of blank lines longer than `max`.
+///
+/// Blank lines are kept in whitespace sensitive regions: the content of `<pre>` and `<textarea>`
+/// elements, comments, and tags and interpolations, whose attribute values and expressions may
+/// contain multi-line strings.
+fn collapse_blank_lines(text: &str, max: usize) -> String {
+    let mut buffer = String::with_capacity(text.len());
+    let mut blank_lines = 0;
+    let mut region = TemplateRegion::Text;
+
+    for line in text.split_inclusive('\n') {
+        if line.trim().is_empty() && region == TemplateRegion::Text {
+            blank_lines += 1;
+
+            if blank_lines > max {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+
+        region = region.advance(line);
+        buffer.push_str(line);
+    }
+
+    buffer
+}
+
+/// Elements whose content is whitespace sensitive.
+const RAW_TEXT_ELEMENTS: [&str; 2] = ["pre", "textarea"];
+
+/// Region of a template at the start of a line, see [`collapse_blank_lines`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TemplateRegion {
+    Text,
+    Comment,
+    /// A `{{ }}` interpolation, `quote` being the quote of the string being scanned.
+    Interpolation {
+        quote: Option<u8>,
+    },
+    /// A start or end tag, `quote` being the quote of the attribute value being scanned and
+    /// `raw_text` the name of the element if it is one of [`RAW_TEXT_ELEMENTS`].
+    Tag {
+        quote: Option<u8>,
+        raw_text: Option<&'static str>,
+    },
+    /// The content of an element of [`RAW_TEXT_ELEMENTS`].
+    RawText(&'static str),
+}
+
+impl TemplateRegion {
+    /// Whether the indentation of a line starting in this region is part of its value.
+    fn is_literal(self) -> bool {
+        matches!(
+            self,
+            Self::Comment
+                | Self::Tag { quote: Some(_), .. }
+                | Self::Interpolation { quote: Some(_) }
+                | Self::RawText(_)
+        )
+    }
+
+    /// Return the region at the end of `line`, which starts in this region.
+    fn advance(mut self, line: &str) -> Self {
+        let bytes = line.as_bytes();
+        let mut index = 0;
+
+        while let Some(&byte) = bytes.get(index) {
+            let rest = &bytes[index..];
+
+            self = match self {
+                Self::Text if rest.starts_with(b"<!--") => {
+                    index += 3;
+                    Self::Comment
+                }
+                Self::Text if rest.starts_with(b"{{") => {
+                    index += 1;
+                    Self::Interpolation { quote: None }
+                }
+                Self::Text
+                    if byte == b'<'
+                        && rest
+                            .get(1)
+                            .is_some_and(|&next| next.is_ascii_alphabetic() || next == b'/') =>
+                {
+                    Self::Tag {
+                        quote: None,
+                        raw_text: RAW_TEXT_ELEMENTS
+                            .into_iter()
+                            .find(|name| starts_with_tag_name(&rest[1..], name)),
+                    }
+                }
+                Self::Comment if rest.starts_with(b"-->") => {
+                    index += 2;
+                    Self::Text
+                }
+                Self::Interpolation { quote: None } => match byte {
+                    b'"' | b'\'' | b'`' => Self::Interpolation { quote: Some(byte) },
+                    b'}' if rest.starts_with(b"}}") => {
+                        index += 1;
+                        Self::Text
+                    }
+                    _ => self,
+                },
+                Self::Interpolation { quote: Some(quote) } => match byte {
+                    b'\\' => {
+                        index += 1;
+                        self
+                    }
+                    _ if byte == quote => Self::Interpolation { quote: None },
+                    _ => self,
+                },
+                Self::Tag {
+                    quote: Some(quote),
+                    raw_text,
+                } if byte == quote => Self::Tag {
+                    quote: None,
+                    raw_text,
+                },
+                Self::Tag {
+                    quote: None,
+                    raw_text,
+                } => match byte {
+                    b'"' | b'\'' => Self::Tag {
+                        quote: Some(byte),
+                        raw_text,
+                    },
+                    b'>' => match raw_text {
+                        Some(name) if index == 0 || bytes[index - 1] != b'/' => Self::RawText(name),
+                        _ => Self::Text,
+                    },
+                    _ => self,
+                },
+                Self::RawText(name)
+                    if rest
+                        .strip_prefix(b"</")
+                        .is_some_and(|end_tag| starts_with_tag_name(end_tag, name)) =>
+                {
+                    Self::Tag {
+                        quote: None,
+                        raw_text: None,
+                    }
+                }
+                _ => self,
+            };
+
+            index += 1;
+        }
+
+        self
+    }
+}
+
+/// Whether `input` starts with the tag name `name`, compared case-insensitively.
+fn starts_with_tag_name(input: &[u8], name: &str) -> bool {
+    input.len() >= name.len()
+        && input[..name.len()].eq_ignore_ascii_case(name.as_bytes())
+        && input
+            .get(name.len())
+            .is_none_or(|&byte| byte.is_ascii_whitespace() || byte == b'>' || byte == b'/')
+}
+
+/// Remove one level of indentation from the children of the root element of a template.
+///
+/// The lines starting inside whitespace sensitive regions, such as the content of a `<pre>` element
+/// or a multi-line attribute value, are left as is, see [`TemplateRegion::is_literal`].
+///
+/// Return `None` if the template does not consist of a single root element whose start and end
+/// tags are on their own lines.
+fn dedent_root_children(text: &str) -> Option<String> {
+    let text = text.trim();
+
+    let mut lines = text.lines();
+    let first = lines.next()?;
+    let last = lines.next_back()?;
+
+    let name = first
+        .strip_prefix('<')?
+        .strip_suffix('>')
+        .filter(|tag| !tag.ends_with('/'))?
+        .split(|char: char| char.is_ascii_whitespace())
+        .next()?;
+
+    let mut region = TemplateRegion::Text.advance(first);
+    let children = lines
+        .map(|line| {
+            let literal = region.is_literal();
+            region = region.advance(line);
+            (line, literal)
+        })
+        .collect::<Vec<_>>();
+
+    if region.is_literal()
+        || !last
+            .strip_prefix("</")
+            .and_then(|tag| tag.strip_suffix('>'))
+            .is_some_and(|tag| tag.trim_end().eq_ignore_ascii_case(name))
+    {
+        return None;
+    }
+
+    let markup = children
+        .iter()
+        .filter(|(_, literal)| !literal)
+        .map(|(line, _)| *line)
+        .collect::<Vec<_>>();
+    let indent = detect_indentation(&markup.join("\n")).width;
+
+    if indent == 0 {
+        return None;
+    }
+
+    let mut buffer = String::with_capacity(text.len());
+
+    buffer.push_str(first);
+    buffer.push('\n');
+
+    for (line, literal) in children {
+        buffer.push_str(if literal {
+            line
+        } else {
+            line.get(indent..).unwrap_or_default()
+        });
+        buffer.push('\n');
+    }
+
+    buffer.push_str(last);
+    buffer.push('\n');
+
+    Some(buffer)
+}
+
 #[cfg(test)]
 mod test {
+    use std::borrow::Cow;
     use std::path::{Path, PathBuf};
 
-    use crate::configuration::Configuration;
+    use dprint_core::configuration::{ConfigKeyMap, ConfigKeyValue};
+
+    use crate::configuration::{Configuration, NewLineKind};
 
-    use super::format;
+    use super::{
+        collapse_blank_lines, dedent_root_children, format, format_block_content, format_file,
+        is_snippet, normalize_line_breaks, trim_blank_lines,
+    };
 
     #[test]
     fn test_format_with_host() {
@@ -105,6 +786,7 @@ mod test {
             indent_template: true,
             use_tabs: false,
             indent_width: 2,
+            ..Configuration::default()
         };
 
         let raw = "<template></template><script></script>";
@@ -128,6 +810,7 @@ mod test {
             indent_template: true,
             use_tabs: false,
             indent_width: 2,
+            ..Configuration::default()
         };
 
         let raw = "<template><div></div></template>";
@@ -139,4 +822,981 @@ mod test {
 
         assert_eq!(pretty, "<template>\n  <div></div>\n</template>");
     }
+
+    #[test]
+    fn test_use_tabs() {
+        let config = Configuration {
+            indent_template: true,
+            use_tabs: true,
+            indent_width: 4,
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n  <div>\n    <p></p>\n  </div>\n</template>\n";
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            |_, raw, override_config| {
+                assert_eq!(
+                    override_config.get("useTabs"),
+                    Some(&ConfigKeyValue::from_bool(true))
+                );
+                Ok(raw.replace("  ", "\t"))
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            pretty,
+            "<template>\n\t<div>\n\t\t<p></p>\n\t</div>\n</template>\n"
+        );
+
+        // The content of blocks which are not indented is formatted with the global `useTabs`.
+        let raw = "<script>\nlet a;\n</script>\n";
+
+        format(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            |_, raw, override_config| {
+                assert_eq!(override_config.get("useTabs"), None);
+                Ok(raw)
+            },
+        )
+        .unwrap();
+
+        let config = Configuration {
+            indent_script_and_style: true,
+            ..config
+        };
+
+        // The content returned by child plugins is only prefixed with the added indentation, the
+        // lines of template literals are left as is.
+        let raw = "<script>\nconst a = `\n    b\n`;\n</script>\n";
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(pretty, "<script>\n\tconst a = `\n    b\n`;\n</script>\n");
+    }
+
+    #[test]
+    fn test_indent_script_and_style() {
+        let config = Configuration {
+            indent_template: false,
+            indent_script_and_style: true,
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n<style>\na {}\n</style>\n<i18n>\n{}\n</i18n>\n";
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(
+            pretty,
+            "<template>\n<div></div>\n</template>\n<script>\n  let a;\n</script>\n<style>\n  a {}\n</style>\n<i18n>\n{}\n</i18n>\n"
+        );
+    }
+
+    #[test]
+    fn test_indent_content() {
+        let config = Configuration {
+            indent_template: false,
+            indent_script_and_style: true,
+            template_indent_content: Some(true),
+            style_indent_content: Some(false),
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n<style>\na {}\n</style>\n";
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(
+            pretty,
+            "<template>\n  <div></div>\n</template>\n<script>\n  let a;\n</script>\n<style>\na {}\n</style>\n"
+        );
+    }
+
+    #[test]
+    fn test_dedent_content() {
+        let config = Configuration {
+            indent_template: false,
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw = "<script>\n    let a;\n\n    if (a) {\n      a();\n    }\n</script>\n";
+        let mut received = Vec::new();
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| {
+            received.push(raw.clone());
+            Ok(raw)
+        })
+        .unwrap();
+
+        assert_eq!(received, ["let a;\n\nif (a) {\n  a();\n}\n"]);
+        assert_eq!(
+            pretty,
+            "<script>\nlet a;\n\nif (a) {\n  a();\n}\n</script>\n"
+        );
+
+        let config = Configuration {
+            indent_script_and_style: true,
+            ..config
+        };
+
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+        let expected = "<script>\n  let a;\n\n  if (a) {\n    a();\n  }\n</script>\n";
+
+        assert_eq!(pretty, expected);
+        assert_eq!(
+            format(Path::new("file.vue"), &pretty, &config, |_, raw, _| Ok(raw)).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_dedent_content_template_literal() {
+        let config = Configuration {
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        // The lines starting inside template literals and strings keep their indentation, which is
+        // part of their value.
+        let raw = "<script>\n  const a = `\n    b\n  `;\n  const c = 'd\\\n    e';\n</script>\n";
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(
+            pretty,
+            "<script>\nconst a = `\n    b\n  `;\nconst c = 'd\\\n    e';\n</script>\n"
+        );
+
+        let template_config = Configuration {
+            indent_template: false,
+            ..config.clone()
+        };
+        let raw = "<template>\n  <pre>\n    a\n  </pre>\n  <p title=\"b\n    c\" />\n</template>\n";
+        let pretty = format(Path::new("file.vue"), raw, &template_config, |_, raw, _| {
+            Ok(raw)
+        })
+        .unwrap();
+
+        assert_eq!(
+            pretty,
+            "<template>\n<pre>\n    a\n  </pre>\n<p title=\"b\n    c\" />\n</template>\n"
+        );
+
+        let config = Configuration {
+            indent_script_and_style: true,
+            ..config
+        };
+
+        let raw = "<script>\n  const a = `\n    b\n  `;\n</script>\n";
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(pretty, raw);
+    }
+
+    #[test]
+    fn test_line_width() {
+        let config = Configuration {
+            indent_template: true,
+            use_tabs: false,
+            indent_width: 4,
+            line_width: 80,
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n";
+        let mut received = Vec::new();
+
+        format(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            |path: &Path, raw, override_config: &ConfigKeyMap| {
+                received.push((
+                    path.to_path_buf(),
+                    override_config.get("lineWidth").cloned(),
+                ));
+                Ok(raw)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            received,
+            [
+                (
+                    PathBuf::from("file.vue.html"),
+                    Some(ConfigKeyValue::from_i32(76))
+                ),
+                (PathBuf::from("file.vue.js"), None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_new_line_kind() {
+        let raw = "<template>\r\n<div></div>\r\n</template>\n<script>\nlet a;\n</script>\r\n";
+        let format_with_host =
+            |_: &Path, raw: String, _: &ConfigKeyMap| Ok(raw.replace("\r\n", "\n"));
+
+        let config = Configuration {
+            indent_template: false,
+            new_line_kind: Some(NewLineKind::Lf),
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            "<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n"
+        );
+
+        let config = Configuration {
+            new_line_kind: Some(NewLineKind::Auto),
+            ..config
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, format_with_host).unwrap(),
+            "<template>\r\n<div></div>\r\n</template>\r\n<script>\r\nlet a;\r\n</script>\r\n"
+        );
+    }
+
+    #[test]
+    fn test_final_newline() {
+        let config = Configuration {
+            indent_template: false,
+            final_newline: true,
+            ..Configuration::default()
+        };
+
+        for raw in [
+            "<template>\n<div></div>\n</template>",
+            "<template>\n<div></div>\n</template>\n\n \n",
+        ] {
+            assert_eq!(
+                format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+                "<template>\n<div></div>\n</template>\n"
+            );
+        }
+
+        let config = Configuration {
+            final_newline: false,
+            ..config
+        };
+
+        assert_eq!(
+            format(
+                Path::new("file.vue"),
+                "<template>\n<div></div>\n</template>",
+                &config,
+                |_, raw, _| Ok(raw)
+            )
+            .unwrap(),
+            "<template>\n<div></div>\n</template>"
+        );
+    }
+
+    #[test]
+    fn test_normalize_line_breaks() {
+        assert!(matches!(
+            normalize_line_breaks("a\nb\n", "\n"),
+            Cow::Borrowed("a\nb\n")
+        ));
+        assert_eq!(
+            normalize_line_breaks("a\nb\r\nc\rd", "\r\n"),
+            "a\r\nb\r\nc\r\nd"
+        );
+        assert_eq!(normalize_line_breaks("\r\n\r\na", "\n"), "\n\na");
+    }
+
+    #[test]
+    fn test_trim_block_content() {
+        let config = Configuration {
+            indent_template: false,
+            trim_block_content: true,
+            ..Configuration::default()
+        };
+
+        let raw = "<script>\n\n\n  let a;\n\n</script>\n<i18n>\n \n{}\n\n\n</i18n>\n";
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            "<script>\nlet a;\n</script>\n<i18n>\n{}\n</i18n>\n"
+        );
+    }
+
+    #[test]
+    fn test_trim_blank_lines() {
+        assert_eq!(trim_blank_lines("\n \n  a\n\n b\n\n"), Some("  a\n\n b"));
+        assert_eq!(trim_blank_lines("\r\n  a\r\n"), Some("  a"));
+        assert_eq!(trim_blank_lines("  a\n b\n"), None);
+        assert_eq!(trim_blank_lines("a"), None);
+        assert_eq!(trim_blank_lines(""), None);
+    }
+
+    #[test]
+    fn test_collapse_blank_lines() {
+        assert_eq!(
+            collapse_blank_lines("<div></div>\n\n\n \n<div></div>\n", 1),
+            "<div></div>\n\n<div></div>\n"
+        );
+
+        assert_eq!(
+            collapse_blank_lines("<div></div>\n\n\n<div></div>\n", 0),
+            "<div></div>\n<div></div>\n"
+        );
+
+        assert_eq!(
+            collapse_blank_lines("<div></div>\n\n\n<div></div>\n", 2),
+            "<div></div>\n\n\n<div></div>\n"
+        );
+
+        let cases = [
+            "<pre>\na\n\n\nb</pre>\n",
+            "<PRE class=\"a\">\n\n\n</PRE>\n",
+            "<textarea>\n\n\n</textarea>\n",
+            "<!--\n\n\n-->\n",
+            "<div :title=\"`a\n\n\nb`\"></div>\n",
+            "{{ `a\n\n\nb` }}\n",
+        ];
+
+        for text in cases {
+            assert_eq!(collapse_blank_lines(text, 0), text, "{text:?}");
+        }
+
+        assert_eq!(
+            collapse_blank_lines(
+                "<pre>a</pre>\n\n\n<pre />\n\n\n<p>{{ a }}</p>\n\n\n<p></p>\n",
+                1
+            ),
+            "<pre>a</pre>\n\n<pre />\n\n<p>{{ a }}</p>\n\n<p></p>\n"
+        );
+    }
+
+    #[test]
+    fn test_multiple_style_blocks() {
+        let config = Configuration {
+            indent_template: true,
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw =
+            "<style lang=\"scss\" scoped>\n.a { .b {} }\n</style>\n\n<style>\n.c {}\n</style>\n";
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            |path, content, config| {
+                buffer.push((path.to_owned(), content.clone(), config.clone()));
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+
+        assert_eq!(buffer.len(), 2);
+
+        assert_eq!(buffer[0].0, PathBuf::from("file.vue.scss"));
+        assert_eq!(buffer[0].1, ".a { .b {} }\n");
+        assert!(buffer[0].2.is_empty());
+
+        assert_eq!(buffer[1].0, PathBuf::from("file.vue.css"));
+        assert_eq!(buffer[1].1, ".c {}\n");
+        assert!(buffer[1].2.is_empty());
+    }
+
+    #[test]
+    fn test_module_flavored_script_langs() {
+        let config = Configuration::default();
+
+        let raw = "<script lang=\"mts\"></script>\n<script lang=\"cts\"></script>";
+
+        let mut buffer = Vec::new();
+
+        format(Path::new("file.vue"), raw, &config, |path, content, _| {
+            buffer.push(path.to_owned());
+            Ok(content)
+        })
+        .unwrap();
+
+        assert_eq!(
+            buffer,
+            [PathBuf::from("file.vue.mts"), PathBuf::from("file.vue.cts")]
+        );
+    }
+
+    #[test]
+    fn test_script_default_lang() {
+        let config = Configuration {
+            script_default_lang: String::from("ts"),
+            ..Configuration::default()
+        };
+
+        let raw = "<script></script><script lang=\"js\"></script>";
+
+        let mut buffer = Vec::new();
+
+        format(Path::new("file.vue"), raw, &config, |path, content, _| {
+            buffer.push(path.to_owned());
+            Ok(content)
+        })
+        .unwrap();
+
+        assert_eq!(
+            buffer,
+            [PathBuf::from("file.vue.ts"), PathBuf::from("file.vue.js")]
+        );
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let config = Configuration {
+            script_pipeline: vec![String::from("{lang}"), String::from("md")],
+            ..Configuration::default()
+        };
+
+        let raw = "<script lang=\"ts\">\nb\n</script>\n<style>\na\n</style>\n";
+
+        let mut buffer = Vec::new();
+
+        // dprint picks the plugin formatting a file by the extension of its path only.
+        let pretty = format(Path::new("file.vue"), raw, &config, |path, content, _| {
+            buffer.push(path.extension().unwrap().to_string_lossy().into_owned());
+            Ok(format!("{content}{}\n", buffer.len()))
+        })
+        .unwrap();
+
+        assert_eq!(
+            pretty,
+            "<script lang=\"ts\">\nb\n1\n2\n</script>\n<style>\na\n3\n</style>\n"
+        );
+        assert_eq!(buffer, ["ts", "md", "css"]);
+    }
+
+    #[test]
+    fn test_template_default_lang() {
+        let config = Configuration {
+            template_default_lang: String::from("pug"),
+            ..Configuration::default()
+        };
+
+        let raw = "<template></template><template lang=\"html\"></template>";
+
+        let mut buffer = Vec::new();
+
+        format(Path::new("file.vue"), raw, &config, |path, content, _| {
+            buffer.push(path.to_owned());
+            Ok(content)
+        })
+        .unwrap();
+
+        assert_eq!(
+            buffer,
+            [
+                PathBuf::from("file.vue.pug"),
+                PathBuf::from("file.vue.html")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_style_default_lang() {
+        let config = Configuration {
+            style_default_lang: String::from("scss"),
+            ..Configuration::default()
+        };
+
+        let raw = "<style></style><style lang=\"css\"></style>";
+
+        let mut buffer = Vec::new();
+
+        format(Path::new("file.vue"), raw, &config, |path, content, _| {
+            buffer.push(path.to_owned());
+            Ok(content)
+        })
+        .unwrap();
+
+        assert_eq!(
+            buffer,
+            [
+                PathBuf::from("file.vue.scss"),
+                PathBuf::from("file.vue.css")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_minified_threshold() {
+        let config = Configuration {
+            minified_threshold: 16,
+            ..Configuration::default()
+        };
+
+        let raw =
+            "<style>\n.a{color:red}.b{color:blue}\n</style>\n<style>\n.a{color:red}\n</style>";
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(Path::new("file.vue"), raw, &config, |_, content, _| {
+            buffer.push(content.clone());
+            Ok(content)
+        })
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+        assert_eq!(buffer, [".a{color:red}\n"]);
+    }
+
+    #[test]
+    fn test_snippet() {
+        let config = Configuration::default();
+
+        assert!(is_snippet(Path::new("file.vue")));
+        assert!(!is_snippet(Path::new("/project/file.vue")));
+        assert!(!is_snippet(Path::new("component.vue")));
+
+        let raw = "<template>\n<div>\n</template>\n<script>\nlet a;\n</script>";
+
+        let mut format_with_host = |path: &Path, raw: String, _: &ConfigKeyMap| {
+            if path.extension().unwrap() == "html" {
+                anyhow::bail!("unclosed element")
+            }
+
+            Ok(raw)
+        };
+
+        let (pretty, _) = format_file(raw, &config, true, |block, _| {
+            format_block_content(block, &config, &mut format_with_host)
+        })
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+
+        assert!(format(Path::new("file.vue"), raw, &config, format_with_host).is_err());
+    }
+
+    #[test]
+    fn test_dedent_root_children() {
+        assert_eq!(
+            dedent_root_children("<div class=\"a\">\n  <p>\n    text\n  </p>\n\n  <p />\n</div>\n"),
+            Some(String::from(
+                "<div class=\"a\">\n<p>\n  text\n</p>\n\n<p />\n</div>\n"
+            ))
+        );
+
+        assert_eq!(dedent_root_children("<div></div>\n<div></div>\n"), None);
+        assert_eq!(
+            dedent_root_children("<div\n  class=\"a\"\n>\n</div>\n"),
+            None
+        );
+        assert_eq!(
+            dedent_root_children("<div>\n  <pre>\n  a</pre>\n</div>\n"),
+            Some(String::from("<div>\n<pre>\n  a</pre>\n</div>\n"))
+        );
+        assert_eq!(
+            dedent_root_children(concat!(
+                "<div>\n  <p\n    title=\"a\n  b\"\n  >\n",
+                "    {{ `c\n  d` }}\n  </p>\n  <!--\n    e\n  -->\n</div>\n",
+            )),
+            Some(String::from(concat!(
+                "<div>\n<p\n  title=\"a\n  b\"\n>\n",
+                "  {{ `c\n  d` }}\n</p>\n<!--\n    e\n  -->\n</div>\n",
+            )))
+        );
+        assert_eq!(dedent_root_children("<div>\n  <p title=\"\n</div>\n"), None);
+    }
+
+    #[test]
+    fn test_directive_comments() {
+        let config = Configuration {
+            indent_width: 2,
+            template_max_blank_lines: 0,
+            template_body_indent: false,
+            ..Configuration::default()
+        };
+
+        let raw = concat!(
+            "<template>\n<div>\n\n",
+            "  <!-- eslint-disable-next-line vue/no-v-html -->\n",
+            "  <p v-html=\"a\"></p>\n",
+            "</div>\n</template>\n",
+        );
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            concat!(
+                "<template>\n  <div>\n",
+                "  <!-- eslint-disable-next-line vue/no-v-html -->\n",
+                "  <p v-html=\"a\"></p>\n",
+                "  </div>\n</template>\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_unmatched_end_tag() {
+        let config = Configuration {
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw = "<template><div></div></template>\n</div>\n<script>\na\n</script>\n";
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            "<template>\n  <div></div>\n</template>\n</div>\n<script>\na\n</script>\n"
+        );
+    }
+
+    #[test]
+    fn test_auto_close_final_block() {
+        let config = Configuration {
+            auto_close_final_block: true,
+            ..Configuration::default()
+        };
+
+        let raw = "<script>\na\n</script>\n\n<style>\nb\n";
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap(),
+            "<script>\na\n</script>\n\n<style>\nb\n</style>\n"
+        );
+
+        assert!(format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |_, raw, _| Ok(raw)
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_compat_version() {
+        let config = Configuration {
+            indent_width: 2,
+            compat_version: Some(String::from("0.3")),
+            template_max_blank_lines: u8::MAX,
+            ..Configuration::default()
+        };
+
+        let raw =
+            "<template><div></div>\n\n\n<div></div></template>\n<script lang= >\na\n</script>\n";
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(format!("{raw}\n"))).unwrap(),
+            "<template>\n  <div></div>\n  \n  \n  <div></div>\n</template>\n<script lang= >\na\n</script>\n"
+        );
+
+        let config = Configuration {
+            use_tabs: true,
+            indent_width: 2,
+            new_line_kind: None,
+            trim_trailing_whitespace: false,
+            indent_script_and_style: true,
+            compat_version: Some(String::from("0.3")),
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n  <div></div>\n</template> \n<script>\n  a;\n</script>\n";
+
+        assert_eq!(
+            format(
+                Path::new("file.vue"),
+                raw,
+                &config,
+                |_, raw, override_config| {
+                    assert!(override_config.is_empty());
+                    Ok(raw)
+                }
+            )
+            .unwrap(),
+            "<template>\n\t\t<div></div>\n</template> \n<script>\n\t\ta;\n</script>\n"
+        );
+    }
+
+    #[test]
+    fn test_generic() {
+        let raw = "<script setup lang=\"ts\" generic=\"T extends Record<string, U>, U\">\nlet a: T;\n</script>\n";
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |path, content, _| {
+                buffer.push(path.to_owned());
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+        assert_eq!(buffer, [PathBuf::from("file.vue.ts")]);
+    }
+
+    #[test]
+    fn test_self_closing_block() {
+        let raw = "<template src=\"./template.html\" />\n\n<script>\na\n</script>\n<style src=\"./style.css\"/>\n";
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |path, content, _| {
+                buffer.push(path.to_owned());
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+        assert_eq!(buffer, [PathBuf::from("file.vue.js")]);
+    }
+
+    #[test]
+    fn test_external_block() {
+        let raw = "<script src=\"./a.ts\"></script>\n<style src=\"./a.css\">\na {}\n</style>\n";
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |path, content, _| {
+                buffer.push(path.to_owned());
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            pretty,
+            "<script src=\"./a.ts\">\n</script>\n<style src=\"./a.css\">\na {}\n</style>\n"
+        );
+        assert_eq!(buffer, [PathBuf::from("file.vue.css")]);
+    }
+
+    #[test]
+    fn test_script_type() {
+        let raw = concat!(
+            "<script type=\"module\">\nlet a;\n</script>\n",
+            "<script type=\"text/typescript\">\nlet b;\n</script>\n",
+            "<script type=\"text/x-template\" id=\"a\">\n<div></div>\n</script>\n",
+            "<script type=\"module\" lang=\"ts\">\nlet c;\n</script>\n",
+        );
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration {
+                lowercase_block_tags: true,
+                ..Configuration::default()
+            },
+            |path, content, _| {
+                buffer.push(path.to_owned());
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+        assert_eq!(
+            buffer,
+            [
+                PathBuf::from("file.vue.js"),
+                PathBuf::from("file.vue.ts"),
+                PathBuf::from("file.vue.ts")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_byte_order_mark() {
+        let raw = "\u{feff}<script>\nlet a;\n</script>\n";
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |_, content, _| Ok(content),
+        )
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration {
+                keep_byte_order_mark: false,
+                ..Configuration::default()
+            },
+            |_, content, _| Ok(content),
+        )
+        .unwrap();
+
+        assert_eq!(pretty, "<script>\nlet a;\n</script>\n");
+    }
+
+    #[test]
+    fn test_blank_file() {
+        for raw in ["", "\n", "\n  \n\n", "\u{feff}\n"] {
+            let format_blank = |config: &Configuration| {
+                format(Path::new("file.vue"), raw, config, |_, _, _| {
+                    unreachable!("blank files have no blocks")
+                })
+                .unwrap()
+            };
+
+            assert_eq!(format_blank(&Configuration::default()), raw);
+
+            assert_eq!(
+                format_blank(&Configuration {
+                    blank_file_newline: true,
+                    keep_byte_order_mark: false,
+                    ..Configuration::default()
+                }),
+                "\n"
+            );
+        }
+    }
+
+    #[test]
+    fn test_mixed_case_block_tags() {
+        let raw = "<Template>\n<div></div>\n</TEMPLATE>\n<SCRIPT lang=\"ts\">\nlet a;\n</Script>\n";
+
+        let mut buffer = Vec::new();
+
+        let mut format_with_host = |path: &Path, content: String, _: &ConfigKeyMap| {
+            buffer.push(path.to_owned());
+            Ok(content)
+        };
+
+        assert_eq!(
+            format(
+                Path::new("file.vue"),
+                raw,
+                &Configuration::default(),
+                &mut format_with_host
+            )
+            .unwrap(),
+            "<Template>\n    <div></div>\n</TEMPLATE>\n<SCRIPT lang=\"ts\">\nlet a;\n</Script>\n"
+        );
+
+        let config = Configuration {
+            lowercase_block_tags: true,
+            ..Configuration::default()
+        };
+
+        assert_eq!(
+            format(Path::new("file.vue"), raw, &config, &mut format_with_host).unwrap(),
+            "<template>\n    <div></div>\n</template>\n<script lang=\"ts\">\nlet a;\n</script>\n"
+        );
+
+        assert_eq!(
+            buffer,
+            [
+                PathBuf::from("file.vue.html"),
+                PathBuf::from("file.vue.ts"),
+                PathBuf::from("file.vue.html"),
+                PathBuf::from("file.vue.ts"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_comments_between_blocks() {
+        let raw = concat!(
+            "<!-- eslint-disable -->\n<script>\na\n</script>\n\n",
+            "<!--\n<style>\nb\n</style>\n-->\n<style>\nc\n</style>\n",
+        );
+
+        assert_eq!(
+            format(
+                Path::new("file.vue"),
+                raw,
+                &Configuration::default(),
+                |_, raw, _| Ok(raw)
+            )
+            .unwrap(),
+            raw
+        );
+    }
+
+    #[test]
+    fn test_end_tag_with_attributes() {
+        let raw = "<script>\na\n</script lang=\"ts\">\n<style>\nb\n</style>\n";
+
+        assert_eq!(
+            format(
+                Path::new("file.vue"),
+                raw,
+                &Configuration::default(),
+                |_, raw, _| Ok(raw)
+            )
+            .unwrap(),
+            raw
+        );
+    }
+
+    #[test]
+    fn test_end_tag_in_script_string() {
+        let raw = "<script>\nconst a = \"</script>\";\n</script>\n<style>\nb\n</style>\n";
+
+        let mut buffer = Vec::new();
+
+        format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |_, content, _| {
+                buffer.push(content.clone());
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(buffer, ["const a = \"</script>\";\n", "b\n"]);
+    }
+
+    #[test]
+    fn test_char_ref_lang() {
+        let raw = "<script lang=\"&#116;s\">\nlet a;\n</script>\n";
+
+        let mut buffer = Vec::new();
+
+        let pretty = format(
+            Path::new("file.vue"),
+            raw,
+            &Configuration::default(),
+            |path, content, _| {
+                buffer.push(path.to_owned());
+                Ok(content)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(pretty, raw);
+        assert_eq!(buffer, [PathBuf::from("file.vue.ts")]);
+    }
 }
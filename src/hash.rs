@@ -0,0 +1,110 @@
+use crate::indentation::detect_indentation;
+use crate::parser::parse_file;
+use crate::parser::Section;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+/// 64-bit FNV-1a, stable across platforms and Rust versions unlike `DefaultHasher`.
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 ^= u64::from(*byte);
+            self.0 = self.0.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    /// Write the non-blank lines of `text` without their indentation and trailing whitespace.
+    fn write_lines(&mut self, text: &str) {
+        for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            self.write(line.as_bytes());
+            self.write(b"\n");
+        }
+    }
+
+    /// Write the content of a block without its common indentation and the blank lines around it,
+    /// other whitespace is significant.
+    fn write_content(&mut self, content: &str) {
+        let dedented = detect_indentation(content).dedent(content);
+
+        for line in dedented
+            .trim_end()
+            .lines()
+            .skip_while(|line| line.trim().is_empty())
+        {
+            self.write(line.as_bytes());
+            self.write(b"\n");
+        }
+    }
+}
+
+/// Return a hash of a Vue SFC which does not change when only the whitespace between its blocks,
+/// the whitespace around the lines of block tags, or the common indentation and surrounding blank
+/// lines of block contents change.
+///
+/// The hash is stable across platforms and versions of this crate sharing the same major version,
+/// so it can be used as a build cache key.
+pub fn content_hash(content: &str) -> anyhow::Result<u64> {
+    let mut hasher = Fnv(FNV_OFFSET_BASIS);
+
+    for section in parse_file(content)? {
+        match section {
+            Section::Raw(text) => hasher.write_lines(text),
+            Section::Block(block) => {
+                hasher.write(b"\0block\n");
+                hasher.write_lines(block.raw_start_tag);
+                hasher.write_content(block.content);
+                hasher.write_lines(block.raw_end_tag);
+            }
+        }
+    }
+
+    Ok(hasher.0)
+}
+
+#[cfg(test)]
+mod test {
+    use super::content_hash;
+
+    #[test]
+    fn test_content_hash() {
+        let hash =
+            content_hash("<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n")
+                .unwrap();
+
+        assert_eq!(
+            hash,
+            content_hash(
+                "<template>\n  <div></div>\n</template>  \n\n\n<script>\n\n    let a;\n\n</script>"
+            )
+            .unwrap()
+        );
+
+        assert_ne!(
+            content_hash("<script>\nlet a = `a  \nb`;\n</script>\n").unwrap(),
+            content_hash("<script>\nlet a = `a\nb`;\n</script>\n").unwrap()
+        );
+
+        assert_ne!(
+            hash,
+            content_hash(
+                "<template>\n<div></div>\n\n</template>\n<script>\nlet a;\n\nlet b;\n</script>\n"
+            )
+            .unwrap()
+        );
+
+        assert_ne!(
+            hash,
+            content_hash("<template>\n<div></div>\n</template>\n<script>\nlet b;\n</script>\n")
+                .unwrap()
+        );
+
+        assert_ne!(
+            hash,
+            content_hash("<template>\n<div></div>\n</template>\n<style>\nlet a;\n</style>\n")
+                .unwrap()
+        );
+    }
+}
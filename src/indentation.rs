@@ -0,0 +1,210 @@
+use std::fmt;
+use std::iter::repeat_n;
+
+/// Indentation of a block of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Indentation {
+    /// Whether the indentation is made of tabs rather than spaces.
+    pub use_tabs: bool,
+    /// Number of tabs or spaces.
+    pub width: usize,
+}
+
+impl Indentation {
+    /// Create an indentation of `width` tabs, or spaces if `use_tabs` is unset.
+    pub const fn new(use_tabs: bool, width: usize) -> Self {
+        Self { use_tabs, width }
+    }
+
+    /// Return the char the indentation is made of, a tab or a space.
+    pub const fn char(&self) -> char {
+        if self.use_tabs {
+            '\t'
+        } else {
+            ' '
+        }
+    }
+
+    /// Prefix every non-blank line of `text` with the indentation, leading blank lines are removed
+    /// and the other blank lines are emptied.
+    pub fn indent(&self, text: &str) -> String {
+        self.indent_except(text, &[])
+    }
+
+    /// Same as [`Indentation::indent`], leaving the lines starting at one of the sorted `offsets`
+    /// as is.
+    pub(crate) fn indent_except(&self, text: &str, offsets: &[usize]) -> String {
+        let mut buffer = String::with_capacity(text.len() + text.lines().count() * self.width);
+
+        for (offset, line) in line_offsets(text) {
+            let content = line.trim_end_matches(['\r', '\n']);
+
+            if offsets.binary_search(&offset).is_ok() {
+                buffer.push_str(content);
+            } else if !content.trim().is_empty() {
+                buffer.extend(repeat_n(self.char(), self.width));
+                buffer.push_str(content);
+            } else if buffer.is_empty() {
+                continue;
+            }
+
+            buffer.push('\n');
+        }
+
+        buffer
+    }
+
+    /// Remove the indentation from the start of every line of `text` starting with it, blank lines
+    /// are emptied.
+    pub fn dedent(&self, text: &str) -> String {
+        self.dedent_except(text, &[])
+    }
+
+    /// Same as [`Indentation::dedent`], leaving the lines starting at one of the sorted `offsets`
+    /// as is.
+    pub(crate) fn dedent_except(&self, text: &str, offsets: &[usize]) -> String {
+        let prefix = self.to_string();
+        let mut buffer = String::with_capacity(text.len());
+
+        for (offset, line) in line_offsets(text) {
+            let content = line.trim_end_matches(['\r', '\n']);
+
+            if offsets.binary_search(&offset).is_ok() {
+                buffer.push_str(line);
+            } else if content.trim().is_empty() {
+                buffer.push_str(&line[content.len()..]);
+            } else {
+                buffer.push_str(line.strip_prefix(prefix.as_str()).unwrap_or(line));
+            }
+        }
+
+        buffer
+    }
+}
+
+impl fmt::Display for Indentation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for _ in 0..self.width {
+            fmt::Write::write_char(f, self.char())?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Detect the base indentation of `text`, the indentation shared by all of its non-blank lines.
+///
+/// Mixed indentation is only considered up to the first char which differs between lines, so
+/// `"\t  a\n\tb"` has a base indentation of one tab.
+pub fn detect_indentation(text: &str) -> Indentation {
+    detect_indentation_except(text, &[])
+}
+
+/// Same as [`detect_indentation`], ignoring the lines starting at one of the sorted `offsets`.
+pub(crate) fn detect_indentation_except(text: &str, offsets: &[usize]) -> Indentation {
+    let mut lines = line_offsets(text)
+        .filter(|(offset, line)| !line.trim().is_empty() && offsets.binary_search(offset).is_err())
+        .map(|(_, line)| line);
+
+    let first = match lines.next() {
+        Some(line) => line,
+        None => return Indentation::default(),
+    };
+
+    let mut prefix = &first[..(first.len() - first.trim_start().len())];
+
+    for line in lines {
+        let common = prefix
+            .bytes()
+            .zip(line.bytes())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        prefix = &prefix[..common];
+    }
+
+    let use_tabs = prefix.starts_with('\t');
+
+    Indentation {
+        use_tabs,
+        width: prefix
+            .chars()
+            .take_while(|&char| char == if use_tabs { '\t' } else { ' ' })
+            .count(),
+    }
+}
+
+/// Return the lines of `text` with their line break and the offset at which they start.
+fn line_offsets(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    text.split_inclusive('\n').scan(0, |offset, line| {
+        let start = *offset;
+        *offset += line.len();
+        Some((start, line))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{detect_indentation, detect_indentation_except, Indentation};
+
+    #[test]
+    fn test_detect_indentation() {
+        assert_eq!(
+            detect_indentation("  a\n\n    b\n  c\n"),
+            Indentation::new(false, 2)
+        );
+        assert_eq!(
+            detect_indentation("\t\ta\n\tb\n"),
+            Indentation::new(true, 1)
+        );
+        assert_eq!(
+            detect_indentation("\t  a\n\tb\n"),
+            Indentation::new(true, 1)
+        );
+        assert_eq!(detect_indentation("  a\n\tb\n"), Indentation::new(false, 0));
+        assert_eq!(detect_indentation("a\n  b\n"), Indentation::new(false, 0));
+        assert_eq!(detect_indentation("\n \n"), Indentation::new(false, 0));
+        assert_eq!(
+            detect_indentation_except("  a = `\nb\n  `;\n", &[8, 10]),
+            Indentation::new(false, 2)
+        );
+    }
+
+    #[test]
+    fn test_indent() {
+        assert_eq!(
+            Indentation::new(false, 2).indent("\n<div>\n  <p />\n</div>"),
+            "  <div>\n    <p />\n  </div>\n"
+        );
+        assert_eq!(
+            Indentation::new(false, 2).indent(" \n  a;\n \n\nb;"),
+            "    a;\n\n\n  b;\n"
+        );
+        assert_eq!(
+            Indentation::new(false, 2).indent_except("a = `\n  \nb`;\nc;", &[6, 9]),
+            "  a = `\n  \nb`;\n  c;\n"
+        );
+    }
+
+    #[test]
+    fn test_dedent() {
+        assert_eq!(
+            Indentation::new(false, 2).dedent("  <div>\n   \n    <p />\n  </div>\n<br>"),
+            "<div>\n\n  <p />\n</div>\n<br>"
+        );
+        assert_eq!(
+            Indentation::new(true, 1).dedent("\ta;\r\n\t\tb;\r\n"),
+            "a;\r\n\tb;\r\n"
+        );
+        assert_eq!(
+            Indentation::new(false, 2).dedent_except("  a = `\n    b\n  `;\n  c;\n", &[8, 14]),
+            "a = `\n    b\n  `;\nc;\n"
+        );
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Indentation::new(false, 2).to_string(), "  ");
+        assert_eq!(Indentation::new(true, 2).to_string(), "\t\t");
+    }
+}
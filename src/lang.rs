@@ -0,0 +1,15 @@
+/// Return the lang of a `<script>` block from its `type` attribute, `None` if it is not a script
+/// such as `text/x-template`.
+pub(crate) fn script_type_lang(script_type: &str) -> Option<&'static str> {
+    match script_type.trim().to_ascii_lowercase().as_str() {
+        "module"
+        | "text/javascript"
+        | "text/ecmascript"
+        | "application/javascript"
+        | "application/ecmascript" => Some("js"),
+        "text/typescript" | "application/typescript" => Some("ts"),
+        "text/babel" | "text/jsx" => Some("jsx"),
+        "application/json" | "application/ld+json" | "importmap" => Some("json"),
+        _ => None,
+    }
+}
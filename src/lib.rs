@@ -1,9 +1,55 @@
-mod configuration;
+#[cfg(feature = "plugin")]
+mod assemble;
+#[cfg(feature = "plugin")]
+mod builder;
+#[cfg(feature = "plugin")]
+mod bytes;
+#[cfg(feature = "plugin")]
+pub mod configuration;
+mod cst;
+mod descriptor;
+#[cfg(feature = "plugin")]
 mod format;
+mod hash;
+mod indentation;
+mod lang;
 mod parser;
+#[cfg(feature = "plugin")]
 mod plugin;
+#[cfg(feature = "plugin")]
+mod range;
+#[cfg(feature = "plugin")]
+mod report;
+mod trivia;
+mod validate;
+mod virtual_document;
+mod visit;
 
-#[cfg(all(target_arch = "wasm32", target_os = "unknown"))]
+#[cfg(feature = "plugin")]
+pub use builder::SfcBuilder;
+#[cfg(feature = "plugin")]
+pub use bytes::{format_bytes, BytesFormatResult};
+pub use cst::{Cst, CstBlock, CstNode, CstToken, CstTokenKind};
+pub use descriptor::{parse_sfc, SfcDescriptor};
+#[cfg(feature = "plugin")]
+pub use format::format;
+pub use hash::content_hash;
+pub use indentation::{detect_indentation, Indentation};
+pub use parser::{
+    Attribute, Block, ParseError, ParseErrorKind, ParseOptions, Section, Sections, Span, StartTag,
+};
+#[cfg(feature = "plugin")]
+pub use plugin::VuePluginHandler;
+#[cfg(feature = "plugin")]
+pub use range::{format_ranges, RangeFormatResult};
+#[cfg(feature = "plugin")]
+pub use report::{format_with_report, BlockReport, FileReport, Normalization, SkipReason};
+pub use trivia::Trivia;
+pub use validate::{validate, Diagnostic};
+pub use virtual_document::{virtual_documents, VirtualDocument};
+pub use visit::{iter_blocks, visit_blocks, visit_sfc, SfcVisitor};
+
+#[cfg(all(feature = "plugin", target_arch = "wasm32", target_os = "unknown"))]
 mod wasm {
     // Ignore warnings generated by the macro.
     #![allow(unused_must_use)]
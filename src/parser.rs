@@ -1,37 +1,396 @@
 mod block;
+mod char_ref;
+mod error;
+mod scan;
 mod section;
+mod span;
 mod util;
 
-pub use block::{Block, StartTag};
+pub use block::{
+    parse_end_tag, parse_start_tag, parse_well_formed_start_tag, Attribute, Block, StartTag,
+};
+pub use error::{ParseError, ParseErrorKind};
 pub use section::Section;
+pub use span::Span;
+pub use util::{line_break_ends, offset_of, parse_declaration};
 
-pub fn parse_file(mut input: &str) -> Result<Vec<Section>, anyhow::Error> {
-    let mut buffer = Vec::new();
+#[cfg(feature = "plugin")]
+pub(crate) use scan::{script_literals, style_literals};
+
+use util::Mismatch;
+
+/// Options of [`parse_file_with`] and [`parse_sections_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Maximum number of elements named after a block that can be open at once in its content,
+    /// such as `<template>` elements in the `<template>` block.
+    pub max_nesting_depth: u16,
+}
 
-    loop {
-        if input.is_empty() {
-            break;
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_nesting_depth: 256,
         }
+    }
+}
+
+/// Parse a Vue SFC, failing if a block is missing its end tag.
+pub fn parse_file(source: &str) -> Result<Vec<Section<'_>>, ParseError> {
+    parse_file_with(source, &ParseOptions::default())
+}
+
+/// See [`parse_file`].
+pub fn parse_file_with<'a>(
+    source: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<Section<'a>>, ParseError> {
+    let sections = parse_sections_with(source, options)?;
+
+    if let Some((offset, start_tag)) = find_unterminated_block(source, &sections) {
+        return Err(ParseError::new(
+            source,
+            offset,
+            Some(start_tag.name),
+            ParseErrorKind::Unterminated,
+        ));
+    }
+
+    Ok(sections)
+}
+
+/// Append the missing end tag of the last block of `source`, if that is the only reason `source`
+/// cannot be parsed.
+#[cfg_attr(not(feature = "plugin"), allow(dead_code))]
+pub fn close_final_block(source: &str) -> Option<String> {
+    let sections = parse_sections(source).ok()?;
+    let (_, start_tag) = find_unterminated_block(source, &sections)?;
+
+    let line_break = util::detect_line_break(source);
+    let mut closed = String::from(source);
+
+    if !closed.ends_with(['\n', '\r']) {
+        closed.push_str(line_break);
+    }
+
+    closed.push_str(&format!("</{}>{line_break}", start_tag.name));
+
+    parse_file(&closed).is_ok().then_some(closed)
+}
+
+/// Return the offset and the start tag of the first block missing its end tag.
+///
+/// Start tags of HTML elements, such as a stray `<br>`, are treated as text. Start tags of custom
+/// blocks, such as `<i18n>`, are treated as text unless nothing but text follows them.
+fn find_unterminated_block<'a>(
+    source: &str,
+    sections: &[Section<'a>],
+) -> Option<(usize, StartTag<'a>)> {
+    sections.iter().enumerate().find_map(|(index, section)| {
+        let Section::Raw(text) = section else {
+            return None;
+        };
+
+        let is_last = sections[index..]
+            .iter()
+            .all(|section| matches!(section, Section::Raw(_)));
 
-        let (remaining, section) =
-            section::parse_section(input).map_err(|err| anyhow::Error::from(err.to_owned()))?;
+        let (offset, start_tag) = find_unterminated_start_tag(text, is_last)?;
 
-        buffer.push(section);
+        Some((offset_of(source, text) + offset, start_tag))
+    })
+}
+
+/// Return the offset and the start tag of the first block of the raw section `text` missing its
+/// end tag, `is_last` being whether only raw sections follow it, see [`find_unterminated_block`].
+pub(crate) fn find_unterminated_start_tag(
+    text: &str,
+    is_last: bool,
+) -> Option<(usize, StartTag<'_>)> {
+    let mut offset = 0;
+
+    while let Some((start, start_tag)) = find_start_tag(&text[offset..]) {
+        if is_block_name(start_tag.name) || (is_last && !is_html_element(start_tag.name)) {
+            return Some((offset + start, start_tag));
+        }
 
-        input = remaining;
+        offset += start + 1;
     }
 
+    None
+}
+
+fn is_block_name(name: &str) -> bool {
+    ["template", "script", "style"]
+        .iter()
+        .any(|block| name.eq_ignore_ascii_case(block))
+}
+
+/// Whether `name` is a standard HTML element, which cannot be the name of a custom block.
+fn is_html_element(name: &str) -> bool {
+    const HTML_ELEMENTS: &[&str] = &[
+        "a",
+        "abbr",
+        "address",
+        "area",
+        "article",
+        "aside",
+        "audio",
+        "b",
+        "base",
+        "bdi",
+        "bdo",
+        "blockquote",
+        "body",
+        "br",
+        "button",
+        "canvas",
+        "caption",
+        "cite",
+        "code",
+        "col",
+        "colgroup",
+        "data",
+        "datalist",
+        "dd",
+        "del",
+        "details",
+        "dfn",
+        "dialog",
+        "div",
+        "dl",
+        "dt",
+        "em",
+        "embed",
+        "fieldset",
+        "figcaption",
+        "figure",
+        "footer",
+        "form",
+        "h1",
+        "h2",
+        "h3",
+        "h4",
+        "h5",
+        "h6",
+        "head",
+        "header",
+        "hgroup",
+        "hr",
+        "html",
+        "i",
+        "iframe",
+        "img",
+        "input",
+        "ins",
+        "kbd",
+        "label",
+        "legend",
+        "li",
+        "link",
+        "main",
+        "map",
+        "mark",
+        "menu",
+        "meta",
+        "meter",
+        "nav",
+        "noscript",
+        "object",
+        "ol",
+        "optgroup",
+        "option",
+        "output",
+        "p",
+        "param",
+        "picture",
+        "pre",
+        "progress",
+        "q",
+        "rp",
+        "rt",
+        "ruby",
+        "s",
+        "samp",
+        "search",
+        "section",
+        "select",
+        "slot",
+        "small",
+        "source",
+        "span",
+        "strong",
+        "sub",
+        "summary",
+        "sup",
+        "table",
+        "tbody",
+        "td",
+        "textarea",
+        "tfoot",
+        "th",
+        "thead",
+        "time",
+        "title",
+        "tr",
+        "track",
+        "u",
+        "ul",
+        "var",
+        "video",
+        "wbr",
+    ];
+
+    HTML_ELEMENTS
+        .iter()
+        .any(|element| name.eq_ignore_ascii_case(element))
+}
+
+/// Parse a Vue SFC, keeping the start tag of blocks missing their end tag in raw sections.
+pub fn parse_sections(source: &str) -> Result<Vec<Section<'_>>, ParseError> {
+    parse_sections_with(source, &ParseOptions::default())
+}
+
+/// See [`parse_sections`].
+pub fn parse_sections_with<'a>(
+    source: &'a str,
+    options: &ParseOptions,
+) -> Result<Vec<Section<'a>>, ParseError> {
+    let mut buffer = Vec::new();
+
+    for_each_section(source, options, |section| buffer.push(section))?;
+
     Ok(buffer)
 }
 
+/// Call `f` with every section of `source` in source order, see [`parse_sections`].
+pub fn for_each_section<'a>(
+    source: &'a str,
+    options: &ParseOptions,
+    mut f: impl FnMut(Section<'a>),
+) -> Result<(), ParseError> {
+    for section in Sections::new(source, *options) {
+        f(section?);
+    }
+
+    Ok(())
+}
+
+/// Iterator over the sections of a Vue SFC in source order, parsing them one at a time instead of
+/// collecting them as [`parse_sections`] does.
+///
+/// Parsing stops after the first error.
+#[derive(Debug, Clone)]
+pub struct Sections<'a> {
+    source: &'a str,
+    input: &'a str,
+    options: ParseOptions,
+}
+
+impl<'a> Sections<'a> {
+    pub fn new(source: &'a str, options: ParseOptions) -> Self {
+        Self {
+            source,
+            input: source,
+            options,
+        }
+    }
+
+    fn error(&self, mismatch: Mismatch) -> ParseError {
+        let offset = offset_of(self.source, self.input);
+
+        match mismatch {
+            Mismatch::TooDeep => ParseError::new(
+                self.source,
+                offset,
+                parse_start_tag(self.input)
+                    .ok()
+                    .map(|(_, start_tag)| start_tag.name),
+                ParseErrorKind::TooDeep {
+                    max_nesting_depth: self.options.max_nesting_depth,
+                },
+            ),
+            Mismatch::Unexpected => {
+                ParseError::new(self.source, offset, None, ParseErrorKind::Unexpected)
+            }
+        }
+    }
+}
+
+impl<'a> Iterator for Sections<'a> {
+    type Item = Result<Section<'a>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.input.is_empty() {
+            return None;
+        }
+
+        let (remaining, mut section) = match section::parse_section(self.input, &self.options) {
+            Ok(parsed) => parsed,
+            Err(mismatch) => {
+                let error = self.error(mismatch);
+                self.input = &self.input[self.input.len()..];
+
+                return Some(Err(error));
+            }
+        };
+
+        if let Section::Block(block) = &mut section {
+            block.shift(offset_of(self.source, self.input));
+        }
+
+        self.input = remaining;
+
+        Some(Ok(section))
+    }
+}
+
+/// Return the offset of the first start tag of `text` outside of comments.
+fn find_start_tag(text: &str) -> Option<(usize, StartTag<'_>)> {
+    let mut offset = 0;
+
+    while let Some(index) = memchr::memchr(b'<', &text.as_bytes()[offset..]) {
+        offset += index;
+
+        let rest = &text[offset..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            offset += 4 + comment.find("-->")? + 3;
+            continue;
+        }
+
+        if let Ok((_, start_tag)) = parse_start_tag(rest) {
+            if start_tag
+                .name
+                .starts_with(|char: char| char.is_ascii_alphabetic())
+            {
+                return Some((offset, start_tag));
+            }
+        }
+
+        offset += 1;
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod test {
+    use std::ops::Range;
+
+    use proptest::prelude::*;
+
+    use crate::cst::Cst;
     use crate::parser::{
         block::{Block, StartTag},
         section::Section,
+        span::Span,
     };
 
-    use super::parse_file;
+    use super::{
+        close_final_block, parse_file, parse_file_with, parse_sections, ParseErrorKind,
+        ParseOptions,
+    };
 
     #[test]
     fn test_parse_file() {
@@ -45,13 +404,321 @@ mod test {
                 Section::Block(Block {
                     start_tag: StartTag {
                         name: "script",
-                        lang: None
+                        lang: None,
+                        src: None,
+                        self_closing: false,
+                        attributes: vec![],
+                        span: Span::new(19, 27)
                     },
                     raw_start_tag: "<script>",
                     raw_end_tag: "</script>",
-                    content: "let value = true;\nconsole.log(value);\n"
+                    content: "let value = true;\nconsole.log(value);\n",
+                    span: Span::new(19, 75)
                 })
             ]
         );
     }
+
+    #[test]
+    fn test_parse_file_unterminated_block() {
+        let source = "<!-- <style> -->\n<script></script>\n\n<template>\n<div></div>\n";
+
+        assert_eq!(
+            parse_file(source).unwrap_err().to_string(),
+            "Unterminated `<template>` block starting at line 4, column 1."
+        );
+
+        assert!(parse_sections(source).is_ok());
+
+        assert!(parse_file("<script></script>\n<p>Stray <br>\n<style></style>\n").is_ok());
+        assert!(parse_file("<script></script>\n<p>Stray <br>\n").is_ok());
+
+        assert_eq!(
+            parse_file("<script></script>\n<i18n>\n{}\n")
+                .unwrap_err()
+                .to_string(),
+            "Unterminated `<i18n>` block starting at line 2, column 1."
+        );
+    }
+
+    #[test]
+    fn test_parse_file_less_than_in_text() {
+        let sections = parse_file("<template>{{ a<b }}</template>").unwrap();
+
+        assert!(matches!(
+            &sections[..],
+            [Section::Block(block)] if block.content == "{{ a<b }}"
+        ));
+
+        let sections = parse_file(
+            "<docs>\nUse a<b for comparisons\n</docs>\n<template><div></div></template>",
+        )
+        .unwrap();
+
+        assert!(matches!(
+            &sections[..],
+            [Section::Block(docs), Section::Raw("\n"), Section::Block(template)]
+                if docs.start_tag.name == "docs"
+                    && docs.content == "Use a<b for comparisons\n"
+                    && template.content == "<div></div>"
+        ));
+    }
+
+    #[test]
+    fn test_parse_file_doctype() {
+        let source = "<?xml version=\"1.0\"?>\n<!DOCTYPE html>\n<template></template>\n";
+
+        assert_eq!(
+            parse_file(source).unwrap()[0],
+            Section::Raw("<?xml version=\"1.0\"?>\n<!DOCTYPE html>\n")
+        );
+    }
+
+    #[test]
+    fn test_parse_file_max_nesting_depth() {
+        let source = "<script></script>\n<template>\n<template><template></template></template>\n</template>\n";
+        let options = ParseOptions {
+            max_nesting_depth: 1,
+        };
+
+        let error = parse_file_with(source, &options).unwrap_err();
+
+        assert_eq!(
+            error.kind,
+            ParseErrorKind::TooDeep {
+                max_nesting_depth: 1
+            }
+        );
+        assert_eq!(
+            error.to_string(),
+            "`<template>` block starting at line 2, column 1 nests elements deeper than 1 levels."
+        );
+
+        assert!(parse_file_with(
+            source,
+            &ParseOptions {
+                max_nesting_depth: 2
+            }
+        )
+        .is_ok());
+
+        let source = format!(
+            "<template>\n{}\n</template>\n",
+            "<template>".repeat(usize::from(u16::MAX) + 1)
+        );
+
+        assert_eq!(
+            parse_file(&source).unwrap_err().kind,
+            ParseErrorKind::TooDeep {
+                max_nesting_depth: 256
+            }
+        );
+    }
+
+    #[test]
+    fn test_close_final_block() {
+        assert_eq!(
+            close_final_block("<script></script>\n<style>\na {}").as_deref(),
+            Some("<script></script>\n<style>\na {}\n</style>\n")
+        );
+
+        assert_eq!(
+            close_final_block("<script></script>\r\n<style>\r\na {}").as_deref(),
+            Some("<script></script>\r\n<style>\r\na {}\r\n</style>\r\n")
+        );
+
+        assert_eq!(
+            close_final_block("<script></script>\r<style>\r").as_deref(),
+            Some("<script></script>\r<style>\r</style>\r")
+        );
+
+        assert_eq!(close_final_block("<script></script>\n"), None);
+        assert_eq!(close_final_block("<script></script>\n<br>\n"), None);
+        assert_eq!(close_final_block("<template>\n<template>\n"), None);
+    }
+
+    #[test]
+    fn test_parse_file_spans() {
+        let source = "<template></template>\n<script lang=\"ts\">\n</script>\n";
+
+        let sections = parse_file(source).unwrap();
+
+        let Section::Block(block) = &sections[2] else {
+            panic!("expected a block");
+        };
+
+        assert_eq!(
+            &source[Range::from(block.span)],
+            "<script lang=\"ts\">\n</script>"
+        );
+        assert_eq!(
+            &source[Range::from(block.start_tag.span)],
+            "<script lang=\"ts\">"
+        );
+        assert_eq!(
+            &source[Range::from(block.start_tag.attributes[0].span)],
+            "lang=\"ts\""
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde() {
+        let sections = parse_file("<script lang=ts></script>").unwrap();
+
+        let json = serde_json::to_string(&sections).unwrap();
+
+        assert_eq!(
+            json,
+            concat!(
+                r#"[{"Block":{"start_tag":{"name":"script","lang":"ts","src":null,"#,
+                r#""self_closing":false,"#,
+                r#""attributes":[{"name":"lang","value":"ts","span":{"start":8,"end":15}}],"#,
+                r#""span":{"start":0,"end":16}},"raw_start_tag":"<script lang=ts>","#,
+                r#""raw_end_tag":"</script>","content":"","span":{"start":0,"end":25}}}]"#,
+            )
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Vec<Section>>(&json).unwrap(),
+            sections
+        );
+    }
+
+    /// A block of a generated document.
+    #[derive(Debug, Clone)]
+    struct GeneratedBlock {
+        start_tag: String,
+        content: String,
+        end_tag: String,
+    }
+
+    fn generated_raw() -> impl Strategy<Value = String> {
+        proptest::collection::vec(
+            proptest::sample::select(vec![
+                "\n",
+                " ",
+                "text",
+                "<!-- comment -->",
+                "<!-- <script> -->",
+            ]),
+            0..4,
+        )
+        .prop_map(|pieces| pieces.concat())
+    }
+
+    fn generated_block() -> impl Strategy<Value = GeneratedBlock> {
+        let name = proptest::sample::select(vec!["template", "script", "style", "i18n", "Docs"]);
+        let attributes = proptest::collection::vec(
+            proptest::sample::select(vec![" lang=\"ts\"", " setup", " scoped", " src='a'"]),
+            0..3,
+        );
+        let content = proptest::collection::vec(
+            proptest::sample::select(vec![
+                "a",
+                " ",
+                "\n",
+                "<div>",
+                "</div>",
+                "<br />",
+                "{{ a < b }}",
+                "<!-- </a> -->",
+                "/* */",
+                "// a\n",
+            ]),
+            0..8,
+        );
+
+        (name, attributes, content, proptest::bool::weighted(0.1)).prop_map(
+            |(name, attributes, content, self_closing)| {
+                let attributes = attributes.concat();
+
+                if self_closing {
+                    GeneratedBlock {
+                        start_tag: format!("<{name}{attributes} />"),
+                        content: String::new(),
+                        end_tag: String::new(),
+                    }
+                } else {
+                    GeneratedBlock {
+                        start_tag: format!("<{name}{attributes}>"),
+                        content: content.concat(),
+                        end_tag: format!("</{name}>"),
+                    }
+                }
+            },
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn test_parse_file_round_trip(
+            first in generated_raw(),
+            parts in proptest::collection::vec((generated_block(), generated_raw()), 0..5),
+        ) {
+            let mut source = first.clone();
+
+            for (block, raw) in &parts {
+                source.push_str(&block.start_tag);
+
+                if !block.end_tag.is_empty() {
+                    source.push('\n');
+                }
+
+                source.push_str(&block.content);
+                source.push_str(&block.end_tag);
+                source.push_str(raw);
+            }
+
+            let sections = parse_file(&source).unwrap();
+
+            let mut expected_raws = vec![first.as_str()];
+            expected_raws.extend(parts.iter().map(|(_, raw)| raw.as_str()));
+            expected_raws.retain(|raw| !raw.is_empty());
+
+            let mut raws = Vec::new();
+            let mut blocks = Vec::new();
+            let mut reassembled = String::new();
+
+            for section in &sections {
+                match section {
+                    Section::Raw(text) => {
+                        raws.push(*text);
+                        reassembled.push_str(text);
+                    }
+                    Section::Block(block) => {
+                        let range = block.range(&source);
+                        let content_range = block.content_range(&source);
+
+                        let trivia = &source[(range.start + block.raw_start_tag.len())..content_range.start];
+
+                        prop_assert!(trivia.is_empty() || trivia == "\n");
+                        prop_assert_eq!(content_range.end + block.raw_end_tag.len(), range.end);
+
+                        reassembled.push_str(block.raw_start_tag);
+                        reassembled.push_str(trivia);
+                        reassembled.push_str(block.content);
+                        reassembled.push_str(block.raw_end_tag);
+
+                        blocks.push((block.raw_start_tag, block.content, block.raw_end_tag));
+                    }
+                }
+            }
+
+            prop_assert_eq!(&reassembled, &source);
+            prop_assert_eq!(Cst::parse(&source).to_string(), source.clone());
+            prop_assert_eq!(raws, expected_raws);
+            prop_assert_eq!(
+                blocks,
+                parts
+                    .iter()
+                    .map(|(block, _)| (
+                        block.start_tag.as_str(),
+                        block.content.as_str(),
+                        block.end_tag.as_str()
+                    ))
+                    .collect::<Vec<_>>()
+            );
+        }
+    }
 }
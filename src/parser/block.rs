@@ -1,39 +1,158 @@
-use nom::{
-    branch::alt,
-    bytes::complete::{tag, take_till, take_until, take_while, take_while1},
-    character::complete::{char, newline},
-    combinator::{consumed, flat_map, opt, recognize},
-    error::ErrorKind,
-    multi::many0,
-    sequence::{delimited, pair, preceded, terminated, tuple},
-    IResult, Parser,
-};
+use std::borrow::Cow;
+use std::ops::Range;
 
-use super::util::is_ascii_whitespace;
+use super::char_ref::decode_char_refs;
+use super::scan::{find_end_tag, find_script_end, find_style_end};
+use super::span::Span;
+use super::util::{
+    offset_of, strip_line_break, take_until_byte, take_until_tag_end, take_while1, trim_whitespace,
+    IResult, Mismatch,
+};
+use super::ParseOptions;
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Block<'a> {
     /// The parsed start tag.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub start_tag: StartTag<'a>,
     /// The start tag as it appears in the source file.
     pub raw_start_tag: &'a str,
     /// The end tag as it appears in the source file.
     pub raw_end_tag: &'a str,
-    /// The content of the block, excluding the first char if it is a newline.
+    /// The content of the block, excluding the line break directly following the start tag.
     pub content: &'a str,
+    /// From the start of the start tag to the end of the end tag.
+    pub span: Span,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct StartTag<'a> {
     /// The tag name such as `template`, `script`, `style`, etc.
     pub name: &'a str,
     /// The `lang` attribute is there is one.
+    #[cfg_attr(feature = "serde", serde(borrow))]
     pub lang: Option<&'a str>,
+    /// The `src` attribute if there is one, referencing a file holding the content of the block.
+    pub src: Option<&'a str>,
+    /// Whether the tag ends with `/>`.
+    pub self_closing: bool,
+    /// The attributes, in source order.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub attributes: Vec<Attribute<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Attribute<'a> {
+    pub name: &'a str,
+    /// The unquoted value, if any.
+    #[cfg_attr(feature = "serde", serde(borrow))]
+    pub value: Option<&'a str>,
+    /// From the start of the name to the end of the value.
+    pub span: Span,
+}
+
+impl<'a> Attribute<'a> {
+    /// The value with its character references decoded, such as `ts` for `&#116;s`.
+    pub fn decoded_value(&self) -> Option<Cow<'a, str>> {
+        self.value.map(decode_char_refs)
+    }
+}
+
+impl<'a> StartTag<'a> {
+    /// Return the value of the first attribute named `name`, `Some(None)` if it has no value.
+    pub fn attribute(&self, name: &str) -> Option<Option<&'a str>> {
+        self.attributes
+            .iter()
+            .find(|attribute| attribute.name.eq_ignore_ascii_case(name))
+            .map(|attribute| attribute.value)
+    }
+
+    /// Whether the tag has a `setup` attribute, as in `<script setup>`.
+    pub fn setup(&self) -> bool {
+        self.attribute("setup").is_some()
+    }
+
+    /// Whether the tag has a `scoped` attribute, as in `<style scoped>`.
+    pub fn scoped(&self) -> bool {
+        self.attribute("scoped").is_some()
+    }
+
+    /// The `lang` attribute with its character references decoded, see [`Attribute::decoded_value`].
+    pub fn decoded_lang(&self) -> Option<Cow<'a, str>> {
+        self.lang.map(decode_char_refs)
+    }
+
+    /// The `type` attribute with its character references decoded, as in `<script type="module">`.
+    pub fn script_type(&self) -> Option<Cow<'a, str>> {
+        self.attribute("type").flatten().map(decode_char_refs)
+    }
+
+    /// The `src` attribute with its character references decoded.
+    pub fn decoded_src(&self) -> Option<Cow<'a, str>> {
+        self.src.map(decode_char_refs)
+    }
+
+    /// The type parameters of `<script setup generic="...">`.
+    pub fn generic(&self) -> Option<&'a str> {
+        self.attribute("generic").flatten()
+    }
+
+    /// The name CSS modules are exposed as for `<style module>`, `$style` if it has no value.
+    pub fn module(&self) -> Option<&'a str> {
+        self.attribute("module")
+            .map(|value| value.unwrap_or("$style"))
+    }
+}
+
+impl<'a> Block<'a> {
+    /// Move the spans of the block `offset` bytes forward.
+    pub(crate) fn shift(&mut self, offset: usize) {
+        self.span = self.span.shift(offset);
+        self.start_tag.span = self.start_tag.span.shift(offset);
+
+        for attribute in &mut self.start_tag.attributes {
+            attribute.span = attribute.span.shift(offset);
+        }
+    }
+
+    /// Return the byte range of the whole block in `source`, which must be the string the block
+    /// was parsed from.
+    pub fn range(&self, source: &str) -> Range<usize> {
+        offset_of(source, self.raw_start_tag)
+            ..offset_of(source, self.raw_end_tag) + self.raw_end_tag.len()
+    }
+
+    /// Return the byte range of the content in `source`, see [`Block::range`].
+    pub fn content_range(&self, source: &str) -> Range<usize> {
+        let start = offset_of(source, self.content);
+
+        start..(start + self.content.len())
+    }
+
+    /// Convert a byte offset in the content of the block to a byte offset in `source`, see
+    /// [`Block::range`].
+    pub fn to_file_position(&self, source: &str, local_offset: usize) -> usize {
+        offset_of(source, self.content) + local_offset
+    }
+
+    /// Convert a byte offset in `source` to a byte offset in the content of the block, if it is
+    /// within the content, see [`Block::range`].
+    pub fn to_block_position(&self, source: &str, file_offset: usize) -> Option<usize> {
+        let range = self.content_range(source);
+
+        (range.start..=range.end)
+            .contains(&file_offset)
+            .then(|| file_offset - range.start)
+    }
 }
 
 /// See <https://html.spec.whatwg.org/multipage/syntax.html#attributes-2>.
 fn parse_attribute_name(input: &str) -> IResult<&str, &str> {
-    take_while1(|char: char| {
+    let is_name_char = |char: char| {
         !matches!(char,
         '\u{007F}'..='\u{009F}'
         | '\u{0020}'
@@ -77,130 +196,231 @@ fn parse_attribute_name(input: &str) -> IResult<&str, &str> {
         | '\u{FFFFF}'
         | '\u{10FFFE}'
         | '\u{10FFFF}')
-    })(input)
+    };
+
+    take_while1(input, is_name_char)
 }
 
 /// See <https://html.spec.whatwg.org/multipage/syntax.html#attributes-2>.
 fn parse_attribute(input: &str) -> IResult<&str, (&str, Option<&str>)> {
-    pair(
-        parse_attribute_name,
-        opt(preceded(
-            tuple((
-                take_while(is_ascii_whitespace),
-                char('='),
-                take_while(is_ascii_whitespace),
-            )),
-            alt((
-                delimited(char('"'), take_until("\""), char('"')),
-                delimited(char('\''), take_until("'"), char('\'')),
-            )),
-        )),
-    )(input)
+    let (rest, name) = parse_attribute_name(input)?;
+
+    let value = trim_whitespace(rest)
+        .strip_prefix('=')
+        .and_then(|value| parse_attribute_value(trim_whitespace(value)).ok());
+
+    match value {
+        Some((rest, value)) => Ok((rest, (name, Some(value)))),
+        None => Ok((rest, (name, None))),
+    }
 }
 
-/// See <https://html.spec.whatwg.org/multipage/syntax.html#start-tags>.
-fn parse_start_tag(input: &str) -> IResult<&str, StartTag> {
-    delimited(
-        char('<'),
-        tuple((
-            take_till(|char: char| char.is_ascii_whitespace() || char == '/' || char == '>'),
-            many0(preceded(take_while(is_ascii_whitespace), parse_attribute)),
-        )),
-        tuple((take_while(is_ascii_whitespace), opt(char('/')), char('>'))),
-    )
-    .map(|(name, attributes)| {
-        let lang = attributes
-            .into_iter()
-            .find_map(|attribute| match attribute {
-                ("lang", Some(lang)) => Some(lang),
-                _ => None,
-            });
-
-        StartTag { name, lang }
+/// Parse a double-quoted, single-quoted or unquoted attribute value, returning it without quotes.
+fn parse_attribute_value(input: &str) -> IResult<&str, &str> {
+    for quote in ['"', '\''] {
+        if let Some(value) = input.strip_prefix(quote) {
+            let (rest, value) = take_until_byte(value, quote as u8)?;
+
+            return Ok((&rest[1..], value));
+        }
+    }
+
+    take_while1(input, |char| {
+        !char.is_ascii_whitespace() && !matches!(char, '"' | '\'' | '=' | '<' | '>' | '`')
     })
-    .parse(input)
+}
+
+/// Parse a start tag, recovering from malformed attributes such as `<script lang= >` by ignoring
+/// everything up to the closing `>`.
+pub fn parse_start_tag(input: &str) -> IResult<&str, StartTag<'_>> {
+    parse_well_formed_start_tag(input).or_else(|_| parse_malformed_start_tag(input))
+}
+
+/// Split `input` after the tag name at its start.
+fn split_tag_name(input: &str) -> (&str, &str) {
+    input.split_at(
+        input
+            .find(|char: char| char.is_ascii_whitespace() || char == '/' || char == '>')
+            .unwrap_or(input.len()),
+    )
+}
+
+/// See <https://html.spec.whatwg.org/multipage/syntax.html#start-tags>.
+pub fn parse_well_formed_start_tag(input: &str) -> IResult<&str, StartTag<'_>> {
+    let (name, mut rest) = split_tag_name(input.strip_prefix('<').ok_or(Mismatch::Unexpected)?);
+
+    let mut attributes = Vec::new();
+
+    while let Ok((after, (name, value))) = parse_attribute(trim_whitespace(rest)) {
+        let start = offset_of(input, trim_whitespace(rest));
+
+        attributes.push(Attribute {
+            name,
+            value,
+            span: Span::new(start, offset_of(input, after)),
+        });
+
+        rest = after;
+    }
+
+    let rest = trim_whitespace(rest);
+    let (rest, self_closing) = match rest.strip_prefix('/') {
+        Some(rest) => (rest, true),
+        None => (rest, false),
+    };
+    let rest = rest.strip_prefix('>').ok_or(Mismatch::Unexpected)?;
+
+    // The first attribute wins, as in browsers.
+    let value_of = |name: &str| {
+        attributes
+            .iter()
+            .find(|attribute| attribute.name.eq_ignore_ascii_case(name))
+            .and_then(|attribute| attribute.value)
+    };
+    let lang = value_of("lang");
+    let src = value_of("src");
+
+    Ok((
+        rest,
+        StartTag {
+            name,
+            lang,
+            src,
+            self_closing,
+            attributes,
+            span: Span::new(0, offset_of(input, rest)),
+        },
+    ))
+}
+
+fn parse_malformed_start_tag(input: &str) -> IResult<&str, StartTag<'_>> {
+    let tag = input.strip_prefix('<').ok_or(Mismatch::Unexpected)?;
+
+    if !tag.starts_with(|char: char| char.is_ascii_alphabetic()) {
+        return Err(Mismatch::Unexpected);
+    }
+
+    let (name, rest) = split_tag_name(tag);
+    let (rest, ignored) = take_until_tag_end(rest)?;
+    let rest = &rest[1..];
+
+    Ok((
+        rest,
+        StartTag {
+            name,
+            lang: None,
+            src: None,
+            self_closing: ignored.ends_with('/'),
+            attributes: Vec::new(),
+            span: Span::new(0, offset_of(input, rest)),
+        },
+    ))
 }
 
 /// Return the string until the corresponding end tag.
-fn parse_tag_content<'a>(tag_name: &'a str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
+///
+/// `line_comments` is whether `//` starts a comment in the content of a `<style>` block, as in Sass
+/// and Less.
+fn parse_tag_content<'a>(
+    tag_name: &'a str,
+    line_comments: bool,
+    max_nesting_depth: u16,
+) -> impl Fn(&'a str) -> IResult<&'a str, &'a str> {
     move |input: &str| {
-        let mut nesting_level = 0u16;
         if input.is_empty() {
-            return Err(nom::Err::Error(nom::error::Error::new(
-                input,
-                ErrorKind::Eof,
-            )));
+            return Err(Mismatch::Unexpected);
         }
 
-        if let Some(mut index) = input.find('<') {
-            while !input[index..].is_empty() {
-                if let Ok((_, start_tag)) = parse_start_tag(&input[index..]) {
-                    if start_tag.name.eq_ignore_ascii_case(tag_name) {
-                        nesting_level += 1;
-                    }
-                } else if let Ok((_, end_tag_name)) = parse_end_tag(&input[index..]) {
-                    if end_tag_name.eq_ignore_ascii_case(tag_name) {
-                        if nesting_level == 0 {
-                            return Ok((&input[index..], &input[..index]));
-                        }
+        let lexed_end = if tag_name.eq_ignore_ascii_case("script") {
+            find_script_end(input)
+        } else if tag_name.eq_ignore_ascii_case("style") {
+            find_style_end(input, line_comments)
+        } else {
+            None
+        };
 
-                        nesting_level -= 1;
-                    }
-                }
+        let end = match lexed_end {
+            Some(end) => end,
+            None => find_end_tag(input, tag_name, max_nesting_depth)?,
+        };
 
-                index += match input.get((index + 1)..).and_then(|input| input.find('<')) {
-                    Some(index) => index + 1,
-                    None => {
-                        return Err(nom::Err::Error(nom::error::Error::new(
-                            input,
-                            ErrorKind::Eof,
-                        )))
-                    }
-                };
-            }
-        }
-
-        Err(nom::Err::Error(nom::error::Error::new(
-            input,
-            ErrorKind::Eof,
-        )))
+        Ok((&input[end..], &input[..end]))
     }
 }
 
 /// See <https://html.spec.whatwg.org/multipage/syntax.html#end-tags>.
-fn parse_end_tag(input: &str) -> IResult<&str, &str> {
-    delimited(
-        tag("</"),
-        take_till(|char: char| char.is_ascii_whitespace() || char == '>'),
-        tuple((take_while(is_ascii_whitespace), char('>'))),
-    )(input)
+///
+/// Anything between the tag name and `>`, such as attributes in `</script lang="ts">`, is ignored.
+pub fn parse_end_tag(input: &str) -> IResult<&str, &str> {
+    let tag = input.strip_prefix("</").ok_or(Mismatch::Unexpected)?;
+
+    let (rest, name) = take_while1(tag, |char| !char.is_ascii_whitespace() && char != '>')?;
+
+    match rest.find(['<', '>']) {
+        Some(close) if rest[close..].starts_with('>') => Ok((&rest[(close + 1)..], name)),
+        _ => Err(Mismatch::Unexpected),
+    }
 }
 
 /// Parse a block such as `<template lang="html"><!-- content --></template>`.
-pub fn parse_block(input: &str) -> IResult<&str, Block> {
-    flat_map(
-        terminated(consumed(parse_start_tag), opt(newline)),
-        |(raw_start_tag, start_tag)| {
-            let tag_name = start_tag.name;
-
-            tuple((parse_tag_content(tag_name), recognize(parse_end_tag))).map(
-                move |(content, raw_end_tag)| Block {
-                    start_tag,
-                    raw_start_tag,
-                    raw_end_tag,
-                    content,
-                },
-            )
+///
+/// A self-closing start tag such as `<style src="./style.css" />` is a complete block with empty
+/// content and an empty end tag.
+pub fn parse_block<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Block<'a>> {
+    let (rest, start_tag) = parse_start_tag(input)?;
+    let raw_start_tag = &input[..offset_of(input, rest)];
+
+    // Such as `<!DOCTYPE html>` or `<?xml version="1.0"?>`.
+    if !start_tag
+        .name
+        .starts_with(|char: char| char.is_ascii_alphabetic())
+    {
+        return Err(Mismatch::Unexpected);
+    }
+
+    if start_tag.self_closing {
+        return Ok((
+            rest,
+            Block {
+                start_tag,
+                raw_start_tag,
+                raw_end_tag: &rest[..0],
+                content: &rest[..0],
+                span: Span::new(0, raw_start_tag.len()),
+            },
+        ));
+    }
+
+    let line_comments = start_tag.decoded_lang().is_some_and(|lang| {
+        ["scss", "sass", "less"]
+            .iter()
+            .any(|line_comment_lang| lang.eq_ignore_ascii_case(line_comment_lang))
+    });
+
+    let rest = strip_line_break(rest);
+    let (rest, content) =
+        parse_tag_content(start_tag.name, line_comments, options.max_nesting_depth)(rest)?;
+    let (after, _) = parse_end_tag(rest)?;
+    let raw_end_tag = &rest[..offset_of(rest, after)];
+
+    Ok((
+        after,
+        Block {
+            start_tag,
+            raw_start_tag,
+            raw_end_tag,
+            content,
+            span: Span::new(0, offset_of(input, after)),
         },
-    )
-    .parse(input)
+    ))
 }
 
 #[cfg(test)]
 mod test {
     use super::{
-        parse_attribute, parse_attribute_name, parse_block, parse_end_tag, parse_start_tag,
-        parse_tag_content, Block, StartTag,
+        parse_attribute, parse_attribute_name, parse_block, parse_end_tag,
+        parse_malformed_start_tag, parse_start_tag, parse_tag_content, parse_well_formed_start_tag,
+        Attribute, Block, ParseOptions, Span, StartTag,
     };
 
     #[test]
@@ -223,6 +443,13 @@ mod test {
         );
 
         assert_eq!(parse_attribute("setup>"), Ok((">", ("setup", None))));
+
+        assert_eq!(
+            parse_attribute("lang=ts setup>"),
+            Ok((" setup>", ("lang", Some("ts"))))
+        );
+
+        assert_eq!(parse_attribute("lang=ts>"), Ok((">", ("lang", Some("ts")))));
     }
 
     #[test]
@@ -233,7 +460,11 @@ mod test {
                 "",
                 StartTag {
                     name: "script",
-                    lang: None
+                    lang: None,
+                    src: None,
+                    self_closing: false,
+                    attributes: vec![],
+                    span: Span::new(0, 8)
                 }
             ))
         );
@@ -244,7 +475,11 @@ mod test {
                 "",
                 StartTag {
                     name: "script",
-                    lang: None
+                    lang: None,
+                    src: None,
+                    self_closing: false,
+                    attributes: vec![],
+                    span: Span::new(0, 9)
                 }
             ))
         );
@@ -255,7 +490,11 @@ mod test {
                 "",
                 StartTag {
                     name: "script",
-                    lang: None
+                    lang: None,
+                    src: None,
+                    self_closing: false,
+                    attributes: vec![],
+                    span: Span::new(0, 9)
                 }
             ))
         );
@@ -266,7 +505,11 @@ mod test {
                 "",
                 StartTag {
                     name: "script",
-                    lang: None
+                    lang: None,
+                    src: None,
+                    self_closing: false,
+                    attributes: vec![],
+                    span: Span::new(0, 10)
                 }
             ))
         );
@@ -277,10 +520,131 @@ mod test {
                 "",
                 StartTag {
                     name: "script",
-                    lang: Some("ts")
+                    lang: Some("ts"),
+                    src: None,
+                    self_closing: false,
+                    attributes: vec![
+                        Attribute {
+                            name: "lang",
+                            value: Some("ts"),
+                            span: Span::new(8, 17)
+                        },
+                        Attribute {
+                            name: "setup",
+                            value: None,
+                            span: Span::new(18, 23)
+                        }
+                    ],
+                    span: Span::new(0, 24)
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_parse_self_closing_start_tag() {
+        for tag in [r#"<style src="./a.css" />"#, r#"<style src="./a.css"/>"#] {
+            assert!(parse_start_tag(tag).unwrap().1.self_closing);
+        }
+
+        assert!(
+            parse_malformed_start_tag("<style src= />")
+                .unwrap()
+                .1
+                .self_closing
+        );
+        assert!(!parse_start_tag("<style>").unwrap().1.self_closing);
+    }
+
+    #[test]
+    fn test_start_tag_attributes() {
+        let (_, start_tag) = parse_start_tag(r#"<script setup lang="ts">"#).unwrap();
+
+        assert!(start_tag.setup());
+        assert!(!start_tag.scoped());
+        assert_eq!(start_tag.attribute("lang"), Some(Some("ts")));
+        assert_eq!(start_tag.attribute("src"), None);
+
+        let (_, start_tag) = parse_start_tag(r#"<script lang="ts" LANG="js">"#).unwrap();
+
+        assert_eq!(start_tag.lang, Some("ts"));
+        assert_eq!(start_tag.attribute("lang"), Some(Some("ts")));
+
+        let (_, start_tag) = parse_start_tag(r#"<script lang="&#116;s">"#).unwrap();
+
+        assert_eq!(start_tag.lang, Some("&#116;s"));
+        assert_eq!(start_tag.decoded_lang().as_deref(), Some("ts"));
+
+        let (_, start_tag) = parse_start_tag("<style scoped module>").unwrap();
+
+        assert!(start_tag.scoped());
+        assert_eq!(start_tag.module(), Some("$style"));
+
+        let (_, start_tag) = parse_start_tag(r#"<style module="classes">"#).unwrap();
+
+        assert_eq!(start_tag.module(), Some("classes"));
+
+        let (_, start_tag) =
+            parse_start_tag(r#"<script setup lang="ts" generic="T extends Record<string, U>, U">"#)
+                .unwrap();
+
+        assert_eq!(start_tag.generic(), Some("T extends Record<string, U>, U"));
+
+        let (_, start_tag) = parse_start_tag(
+            "<script setup\n  lang=\"ts\"\n  generic=\"T extends\n    Record<string, unknown>\"\n>",
+        )
+        .unwrap();
+
+        assert_eq!(start_tag.lang, Some("ts"));
+        assert_eq!(
+            start_tag.generic(),
+            Some("T extends\n    Record<string, unknown>")
+        );
+
+        let (_, start_tag) = parse_start_tag("<script lang=ts setup>").unwrap();
+
+        assert_eq!(start_tag.lang, Some("ts"));
+        assert!(start_tag.setup());
+    }
+
+    #[test]
+    fn test_parse_malformed_start_tag() {
+        for tag in ["<script lang= >", r#"<script lang="ts>"#, "<script =>"] {
+            assert_eq!(
+                parse_start_tag(tag),
+                Ok((
+                    "",
+                    StartTag {
+                        name: "script",
+                        lang: None,
+                        src: None,
+                        self_closing: false,
+                        attributes: vec![],
+                        span: Span::new(0, tag.len())
+                    }
+                ))
+            );
+
+            assert!(parse_well_formed_start_tag(tag).is_err());
+        }
+
+        assert_eq!(
+            parse_malformed_start_tag("<div =a :title=\"'a > b'\">"),
+            Ok((
+                "",
+                StartTag {
+                    name: "div",
+                    lang: None,
+                    src: None,
+                    self_closing: false,
+                    attributes: vec![],
+                    span: Span::new(0, 25)
                 }
             ))
         );
+
+        assert!(parse_malformed_start_tag("<!-- comment -->").is_err());
+        assert!(parse_malformed_start_tag("</script>").is_err());
     }
 
     #[test]
@@ -289,17 +653,22 @@ mod test {
         assert_eq!(parse_end_tag("</script >"), Ok(("", "script")));
         assert_eq!(parse_end_tag("</script\t>"), Ok(("", "script")));
         assert_eq!(parse_end_tag("</script \t>"), Ok(("", "script")));
+        assert_eq!(parse_end_tag("</script lang=\"ts\">"), Ok(("", "script")));
+        assert!(parse_end_tag("</ script>").is_err());
+        assert!(parse_end_tag("</script\n<style>").is_err());
     }
 
     #[test]
     fn test_parse_tag_content() {
         assert_eq!(
-            parse_tag_content("script")("let value = true;\nconsole.log(value);\n</script>"),
+            parse_tag_content("script", false, 8)(
+                "let value = true;\nconsole.log(value);\n</script>"
+            ),
             Ok(("</script>", "let value = true;\nconsole.log(value);\n"))
         );
 
         assert_eq!(
-            parse_tag_content("script")(
+            parse_tag_content("script", false, 8)(
                 "let value = Math.random();\nconsole.log(value < 0.5);\n</script>"
             ),
             Ok((
@@ -309,27 +678,224 @@ mod test {
         );
 
         assert_eq!(
-            parse_tag_content("template")("<template></template></template>"),
+            parse_tag_content("template", false, 8)("<template></template></template>"),
             Ok(("</template>", "<template></template>"))
         );
+
+        assert_eq!(
+            parse_tag_content("template", false, 8)(
+                "<template v-if=\"a\n  </template>\"></template></template>"
+            ),
+            Ok((
+                "</template>",
+                "<template v-if=\"a\n  </template>\"></template>"
+            ))
+        );
+
+        assert_eq!(
+            parse_tag_content("template", false, 8)("<![CDATA[ <template> ]]></template>"),
+            Ok(("</template>", "<![CDATA[ <template> ]]>"))
+        );
+
+        assert_eq!(
+            parse_tag_content("template", false, 8)("<!-- </template> --><div></div></template>"),
+            Ok(("</template>", "<!-- </template> --><div></div>"))
+        );
+
+        assert_eq!(
+            parse_tag_content("template", false, 8)("<div title=\"</template>\"></div></template>"),
+            Ok(("</template>", "<div title=\"</template>\"></div>"))
+        );
+
+        assert_eq!(
+            parse_tag_content("template", false, 8)(
+                "<template =a :title=\"'</template>'\"></template></template>"
+            ),
+            Ok((
+                "</template>",
+                "<template =a :title=\"'</template>'\"></template>"
+            ))
+        );
+
+        assert_eq!(
+            parse_tag_content("template", false, 8)("<template #header /></template>"),
+            Ok(("</template>", "<template #header />"))
+        );
+
+        assert_eq!(
+            parse_tag_content("style", true, 8)(
+                "// don't\n.a::after { content: '</style>'; }\n</style>"
+            ),
+            Ok(("</style>", "// don't\n.a::after { content: '</style>'; }\n"))
+        );
+    }
+
+    #[test]
+    fn test_parse_block_with_malformed_start_tag() {
+        assert_eq!(
+            parse_block(
+                "<script lang= >\nlet value = true;\n</script>",
+                &ParseOptions::default()
+            ),
+            Ok((
+                "",
+                Block {
+                    start_tag: StartTag {
+                        name: "script",
+                        lang: None,
+                        src: None,
+                        self_closing: false,
+                        attributes: vec![],
+                        span: Span::new(0, 15)
+                    },
+                    raw_start_tag: "<script lang= >",
+                    raw_end_tag: "</script>",
+                    content: "let value = true;\n",
+                    span: Span::new(0, 43)
+                }
+            ))
+        );
     }
 
     #[test]
     fn test_parse_block() {
         assert_eq!(
-            parse_block("<script>\nlet value = true;\nconsole.log(value);\n</script>\n<!-- residual data -->"),
+            parse_block("<script>\nlet value = true;\nconsole.log(value);\n</script>\n<!-- residual data -->", &ParseOptions::default()),
             Ok((
                 "\n<!-- residual data -->",
                 Block {
                     start_tag: StartTag {
                         name: "script",
-                        lang: None
+                        lang: None,
+                        src: None,
+                        self_closing: false,
+                        attributes: vec![],
+                        span: Span::new(0, 8)
                     },
                     raw_start_tag: "<script>",
                     raw_end_tag: "</script>",
-                    content: "let value = true;\nconsole.log(value);\n"
+                    content: "let value = true;\nconsole.log(value);\n",
+                    span: Span::new(0, 56)
                 }
             ))
         );
+
+        let (_, block) = parse_block(
+            "<style lang=\"scss\">\n// don't\n.a::after { content: '</style>'; }\n</style>",
+            &ParseOptions::default(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            block.content,
+            "// don't\n.a::after { content: '</style>'; }\n"
+        );
+    }
+
+    #[test]
+    fn test_parse_block_with_generic() {
+        for raw_start_tag in [
+            "<script setup lang=\"ts\" generic=\"T extends Record<string, U>, U\">",
+            "<script setup lang='ts' generic='T extends Map<K, V>, K, V'>",
+            "<script setup generic=\"T extends Record<string, U>, U\" =>",
+        ] {
+            let input = format!("{raw_start_tag}\nlet a: T;\n</script>\n");
+
+            let (rest, block) = parse_block(&input, &ParseOptions::default()).unwrap();
+
+            assert_eq!(rest, "\n");
+            assert_eq!(block.raw_start_tag, raw_start_tag);
+            assert_eq!(block.content, "let a: T;\n");
+            assert_eq!(block.start_tag.span, Span::new(0, raw_start_tag.len()));
+        }
+    }
+
+    #[test]
+    fn test_parse_src() {
+        let (_, start_tag) = parse_start_tag("<script SRC=\"./a&#46;ts\" src=\"b.ts\">").unwrap();
+
+        assert_eq!(start_tag.src, Some("./a&#46;ts"));
+        assert_eq!(start_tag.decoded_src().as_deref(), Some("./a.ts"));
+
+        let (_, start_tag) = parse_start_tag("<script src>").unwrap();
+
+        assert_eq!(start_tag.src, None);
+    }
+
+    #[test]
+    fn test_script_type() {
+        let (_, start_tag) = parse_start_tag("<script TYPE=\"text/ecmascript\">").unwrap();
+
+        assert_eq!(start_tag.script_type().as_deref(), Some("text/ecmascript"));
+
+        let (_, start_tag) = parse_start_tag("<script type>").unwrap();
+
+        assert_eq!(start_tag.script_type(), None);
+    }
+
+    #[test]
+    fn test_parse_block_line_breaks() {
+        for line_break in ["\n", "\r\n", "\r"] {
+            let input = format!("<script>{line_break}let a;{line_break}</script>{line_break}");
+
+            let (rest, block) = parse_block(&input, &ParseOptions::default()).unwrap();
+
+            assert_eq!(rest, line_break);
+            assert_eq!(block.content, format!("let a;{line_break}"));
+        }
+
+        let (_, block) =
+            parse_block("<script>\n\r\nlet a;\n</script>", &ParseOptions::default()).unwrap();
+
+        assert_eq!(block.content, "\r\nlet a;\n");
+    }
+
+    #[test]
+    fn test_parse_self_closing_block() {
+        assert_eq!(
+            parse_block(
+                "<style src=\"./style.css\" />\n<script>",
+                &ParseOptions::default()
+            ),
+            Ok((
+                "\n<script>",
+                Block {
+                    start_tag: StartTag {
+                        name: "style",
+                        lang: None,
+                        src: Some("./style.css"),
+                        self_closing: true,
+                        attributes: vec![Attribute {
+                            name: "src",
+                            value: Some("./style.css"),
+                            span: Span::new(7, 24)
+                        }],
+                        span: Span::new(0, 27)
+                    },
+                    raw_start_tag: "<style src=\"./style.css\" />",
+                    raw_end_tag: "",
+                    content: "",
+                    span: Span::new(0, 27)
+                }
+            ))
+        );
+    }
+
+    #[test]
+    fn test_positions() {
+        let source = "<!-- A comment -->\n<script lang=\"ts\">\nlet value = true;\n</script>";
+
+        let (_, block) = parse_block(&source[19..], &ParseOptions::default()).unwrap();
+
+        assert_eq!(block.range(source), 19..source.len());
+        assert_eq!(block.content_range(source), 38..56);
+
+        assert_eq!(block.to_file_position(source, 4), 42);
+        assert_eq!(&source[42..47], "value");
+
+        assert_eq!(block.to_block_position(source, 42), Some(4));
+        assert_eq!(block.to_block_position(source, 56), Some(18));
+        assert_eq!(block.to_block_position(source, 37), None);
+        assert_eq!(block.to_block_position(source, 57), None);
     }
 }
@@ -0,0 +1,80 @@
+use std::borrow::Cow;
+
+/// Decode the character references of an attribute value, such as `&#116;` or `&quot;`.
+///
+/// Only the named references likely to appear in attribute values are supported, others are kept
+/// as is.
+pub fn decode_char_refs(value: &str) -> Cow<'_, str> {
+    if !value.contains('&') {
+        return Cow::Borrowed(value);
+    }
+
+    let mut buffer = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('&') {
+        buffer.push_str(&rest[..start]);
+        rest = &rest[start..];
+
+        match rest
+            .find(';')
+            .and_then(|end| Some((decode(&rest[1..end])?, end)))
+        {
+            Some((char, end)) => {
+                buffer.push(char);
+                rest = &rest[(end + 1)..];
+            }
+            None => {
+                buffer.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+
+    buffer.push_str(rest);
+
+    Cow::Owned(buffer)
+}
+
+/// Decode a character reference without its leading `&` and trailing `;`.
+fn decode(reference: &str) -> Option<char> {
+    let code = if let Some(hex) = reference
+        .strip_prefix("#x")
+        .or_else(|| reference.strip_prefix("#X"))
+    {
+        u32::from_str_radix(hex, 16).ok()?
+    } else if let Some(decimal) = reference.strip_prefix('#') {
+        decimal.parse().ok()?
+    } else {
+        return match reference {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some('\u{A0}'),
+            _ => None,
+        };
+    };
+
+    char::from_u32(code)
+}
+
+#[cfg(test)]
+mod test {
+    use std::borrow::Cow;
+
+    use super::decode_char_refs;
+
+    #[test]
+    fn test_decode_char_refs() {
+        assert!(matches!(decode_char_refs("ts"), Cow::Borrowed("ts")));
+        assert_eq!(decode_char_refs("&#116;s"), "ts");
+        assert_eq!(decode_char_refs("&#x74;&#X73;"), "ts");
+        assert_eq!(decode_char_refs("&quot;a&quot; &amp;&amp; b"), "\"a\" && b");
+        assert_eq!(
+            decode_char_refs("a & b &unknown; &#xZZ;"),
+            "a & b &unknown; &#xZZ;"
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt;
+
+use super::util::line_break_ends;
+
+/// Error returned when a Vue SFC cannot be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// What went wrong.
+    pub kind: ParseErrorKind,
+    /// Name of the block the error relates to, if any.
+    pub block: Option<String>,
+    /// Line of the error, starting at 1.
+    pub line: usize,
+    /// Column of the error in characters, starting at 1.
+    pub column: usize,
+}
+
+/// The kind of a [`ParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    /// A block is missing its end tag.
+    Unterminated,
+    /// A block contains blocks of the same name nested deeper than `max_nesting_depth`, see
+    /// [`super::ParseOptions`].
+    TooDeep { max_nesting_depth: u16 },
+    /// The input cannot be parsed.
+    Unexpected,
+}
+
+impl ParseError {
+    /// Create an error located at byte `offset` of `source`.
+    pub(crate) fn new(
+        source: &str,
+        offset: usize,
+        block: Option<&str>,
+        kind: ParseErrorKind,
+    ) -> Self {
+        let before = &source[..offset];
+
+        let mut line = 1;
+        let mut line_start = 0;
+
+        for end in line_break_ends(before) {
+            line += 1;
+            line_start = end;
+        }
+
+        Self {
+            kind,
+            block: block.map(String::from),
+            line,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let block = self.block.as_deref().unwrap_or("?");
+
+        match self.kind {
+            ParseErrorKind::Unterminated => write!(
+                f,
+                "Unterminated `<{block}>` block starting at line {}, column {}.",
+                self.line, self.column
+            ),
+            ParseErrorKind::TooDeep { max_nesting_depth } => write!(
+                f,
+                "`<{block}>` block starting at line {}, column {} nests elements deeper than {max_nesting_depth} levels.",
+                self.line, self.column
+            ),
+            ParseErrorKind::Unexpected => write!(
+                f,
+                "Unexpected input at line {}, column {}.",
+                self.line, self.column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[cfg(test)]
+mod test {
+    use super::{ParseError, ParseErrorKind};
+
+    #[test]
+    fn test_parse_error() {
+        let error = ParseError::new(
+            "<script></script>\n  <é><style>",
+            24,
+            Some("style"),
+            ParseErrorKind::Unterminated,
+        );
+
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 6);
+        assert_eq!(
+            error.to_string(),
+            "Unterminated `<style>` block starting at line 2, column 6."
+        );
+
+        let error = ParseError::new(
+            "<script>\r\n</script>\r<é>\n\r\n  <style>",
+            29,
+            Some("style"),
+            ParseErrorKind::Unterminated,
+        );
+
+        assert_eq!((error.line, error.column), (5, 3));
+
+        let error = ParseError::new(
+            "<template>",
+            0,
+            Some("template"),
+            ParseErrorKind::TooDeep {
+                max_nesting_depth: 256,
+            },
+        );
+
+        assert_eq!(
+            error.to_string(),
+            "`<template>` block starting at line 1, column 1 nests elements deeper than 256 levels."
+        );
+    }
+}
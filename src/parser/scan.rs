@@ -0,0 +1,589 @@
+use std::ops::Range;
+
+use super::{block::parse_end_tag, util::Mismatch};
+
+/// Return the offset of the `</script>` end tag of `input`, skipping strings, template literals,
+/// comments and regular expression literals.
+///
+/// Return `None` if the content cannot be lexed up to an end tag, such as when a string is not
+/// terminated.
+pub fn find_script_end(input: &str) -> Option<usize> {
+    lex_script(input, true, |_| {})
+}
+
+/// Return the ranges of the strings and template literals of the script `input`, template literals
+/// being split around their `${}` expressions.
+///
+/// Lexing stops where `input` cannot be lexed, such as at a string which is not terminated.
+#[cfg(feature = "plugin")]
+pub fn script_literals(input: &str) -> Vec<Range<usize>> {
+    let mut literals = Vec::new();
+    lex_script(input, false, |literal| literals.push(literal));
+    literals
+}
+
+/// Lex the script `input`, calling `literal` with the range of every string and template literal,
+/// up to the `</script>` end tag if `end_tag` is set.
+fn lex_script(input: &str, end_tag: bool, mut literal: impl FnMut(Range<usize>)) -> Option<usize> {
+    let bytes = input.as_bytes();
+
+    let mut index = 0;
+    // Brace depth at which each template literal `${` expression being scanned started.
+    let mut expressions = Vec::new();
+    let mut depth = 0usize;
+    let mut regex_allowed = true;
+
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'<' => {
+                if end_tag {
+                    if let Ok((_, name)) = parse_end_tag(&input[index..]) {
+                        if name.eq_ignore_ascii_case("script") {
+                            return Some(index);
+                        }
+                    }
+                }
+
+                index += 1;
+                // `</` is an end tag of markup such as `<!-- </a> -->` rather than a comparison
+                // with a regular expression.
+                regex_allowed = bytes.get(index) != Some(&b'/');
+            }
+            b'"' | b'\'' => {
+                let start = index;
+                index = skip_string(bytes, index)?;
+                literal(start..index);
+                regex_allowed = false;
+            }
+            b'`' => {
+                let start = index;
+                index = skip_template(bytes, index + 1, &mut expressions, depth)?;
+                literal(start..index);
+                regex_allowed = false;
+            }
+            b'{' => {
+                depth += 1;
+                index += 1;
+                regex_allowed = true;
+            }
+            b'}' if expressions.last() == Some(&depth) => {
+                let start = index;
+                expressions.pop();
+                index = skip_template(bytes, index + 1, &mut expressions, depth)?;
+                literal(start..index);
+                regex_allowed = false;
+            }
+            b'}' => {
+                depth = depth.saturating_sub(1);
+                index += 1;
+                regex_allowed = true;
+            }
+            b'/' => match bytes.get(index + 1) {
+                Some(b'/') => {
+                    index = memchr::memchr2(b'\n', b'\r', &bytes[index..])
+                        .map_or(bytes.len(), |end| index + end);
+                }
+                Some(b'*') => {
+                    index += 2 + memchr::memmem::find(&bytes[(index + 2)..], b"*/")? + 2;
+                }
+                _ if regex_allowed => {
+                    index = skip_regex(bytes, index)?;
+                    regex_allowed = false;
+                }
+                _ => {
+                    index += 1;
+                    regex_allowed = true;
+                }
+            },
+            b')' | b']' => {
+                index += 1;
+                regex_allowed = false;
+            }
+            _ if byte.is_ascii_whitespace() => index += 1,
+            _ if is_word_byte(byte) => {
+                let start = index;
+
+                while bytes.get(index).copied().is_some_and(is_word_byte) {
+                    index += 1;
+                }
+
+                regex_allowed = matches!(
+                    &input[start..index],
+                    "return"
+                        | "typeof"
+                        | "instanceof"
+                        | "in"
+                        | "of"
+                        | "new"
+                        | "delete"
+                        | "void"
+                        | "throw"
+                        | "case"
+                        | "do"
+                        | "else"
+                        | "yield"
+                        | "await"
+                );
+            }
+            _ => {
+                index += 1;
+                regex_allowed = true;
+            }
+        }
+    }
+
+    None
+}
+
+/// Return the offset of the `</style>` end tag of `input`, skipping strings and comments.
+///
+/// `//` only starts a comment if `line_comments` is set, since it is not a comment in CSS and
+/// appears in protocol-relative URLs such as `url(//example.com/a.png)`.
+///
+/// Return `None` if the content cannot be lexed up to an end tag, such as when a comment is not
+/// terminated.
+pub fn find_style_end(input: &str, line_comments: bool) -> Option<usize> {
+    lex_style(input, line_comments, true, |_| {})
+}
+
+/// Return the ranges of the strings of the style sheet `input`, see [`script_literals`].
+#[cfg(feature = "plugin")]
+pub fn style_literals(input: &str, line_comments: bool) -> Vec<Range<usize>> {
+    let mut literals = Vec::new();
+    lex_style(input, line_comments, false, |literal| {
+        literals.push(literal)
+    });
+    literals
+}
+
+/// Lex the style sheet `input`, calling `literal` with the range of every string, up to the
+/// `</style>` end tag if `end_tag` is set.
+fn lex_style(
+    input: &str,
+    line_comments: bool,
+    end_tag: bool,
+    mut literal: impl FnMut(Range<usize>),
+) -> Option<usize> {
+    let bytes = input.as_bytes();
+
+    let mut index = 0;
+
+    while let Some(&byte) = bytes.get(index) {
+        match byte {
+            b'<' => {
+                if end_tag {
+                    if let Ok((_, name)) = parse_end_tag(&input[index..]) {
+                        if name.eq_ignore_ascii_case("style") {
+                            return Some(index);
+                        }
+                    }
+                }
+
+                index += 1;
+            }
+            b'"' | b'\'' => {
+                let start = index;
+                index = skip_string(bytes, index)?;
+                literal(start..index);
+            }
+            b'/' => match bytes.get(index + 1) {
+                Some(b'/') if line_comments => {
+                    index = memchr::memchr2(b'\n', b'\r', &bytes[index..])
+                        .map_or(bytes.len(), |end| index + end);
+                }
+                Some(b'*') => {
+                    index += 2 + memchr::memmem::find(&bytes[(index + 2)..], b"*/")? + 2;
+                }
+                _ => index += 1,
+            },
+            _ => index += 1,
+        }
+    }
+
+    None
+}
+
+/// Return the offset of the end tag named `tag_name` closing `input`, skipping comments, CDATA
+/// sections and nested blocks of the same name.
+///
+/// This is a single forward pass: every start tag is skipped as a whole, so `<` and `>` inside its
+/// quoted attribute values are ignored. A `<` followed by a letter which is not closed before the
+/// next `</` is text, as in `{{ a<b }}`.
+///
+/// Fail with [`Mismatch::TooDeep`] if more than `max_nesting_depth` nested blocks are open at once.
+pub fn find_end_tag(
+    input: &str,
+    tag_name: &str,
+    max_nesting_depth: u16,
+) -> Result<usize, Mismatch> {
+    let bytes = input.as_bytes();
+
+    let mut nesting_level = 0u16;
+    let mut index = 0;
+    // Quotes known not to be closed anywhere after `index`, see `find_tag_end`.
+    let mut unclosed_quotes = UnclosedQuotes::default();
+
+    while let Some(offset) = memchr::memchr(b'<', &bytes[index..]) {
+        index += offset;
+
+        let rest = &bytes[index..];
+
+        if let Some(length) = skip_delimited(rest, b"<!--", b"-->")
+            .or_else(|| skip_delimited(rest, b"<![CDATA[", b"]]>"))
+        {
+            index += length;
+        } else if let Some(end_tag) = rest.strip_prefix(b"</") {
+            let name = tag_name_length(end_tag, |byte| byte == b'>');
+
+            // Anything but `<` may follow the name, as in `</script lang="ts">`.
+            let close = end_tag[name..]
+                .iter()
+                .position(|&byte| byte == b'<' || byte == b'>')
+                .filter(|&close| name > 0 && end_tag[name + close] == b'>');
+
+            match close {
+                Some(close) => {
+                    if end_tag[..name].eq_ignore_ascii_case(tag_name.as_bytes()) {
+                        if nesting_level == 0 {
+                            return Ok(index);
+                        }
+
+                        nesting_level -= 1;
+                    }
+
+                    index += 2 + name + close + 1;
+                }
+                None => index += 1,
+            }
+        } else if rest.get(1).is_some_and(u8::is_ascii_alphabetic) {
+            let name = tag_name_length(&rest[1..], |byte| byte == b'/' || byte == b'>');
+            let attributes = &rest[(1 + name)..];
+
+            let end = match find_tag_end(attributes, &mut unclosed_quotes) {
+                TagEnd::Close(end) => end,
+                TagEnd::Text => {
+                    index += 1;
+                    continue;
+                }
+                // Without a `>` or `</`, there is no end tag either.
+                TagEnd::Missing => return Err(Mismatch::Unexpected),
+            };
+
+            let self_closing = attributes[..end].trim_ascii_end().ends_with(b"/");
+
+            if rest[1..(1 + name)].eq_ignore_ascii_case(tag_name.as_bytes()) && !self_closing {
+                if nesting_level == max_nesting_depth {
+                    return Err(Mismatch::TooDeep);
+                }
+
+                nesting_level += 1;
+            }
+
+            index += 1 + name + end + 1;
+        } else {
+            index += 1;
+        }
+    }
+
+    Err(Mismatch::Unexpected)
+}
+
+/// Return the length of `input` if it starts with `open` and contains `close` afterwards.
+fn skip_delimited(input: &[u8], open: &[u8], close: &[u8]) -> Option<usize> {
+    let content = input.strip_prefix(open)?;
+
+    Some(open.len() + memchr::memmem::find(content, close)? + close.len())
+}
+
+/// Return the length of the tag name at the start of `input`.
+fn tag_name_length(input: &[u8], is_end: impl Fn(u8) -> bool) -> usize {
+    input
+        .iter()
+        .position(|&byte| byte.is_ascii_whitespace() || is_end(byte))
+        .unwrap_or(input.len())
+}
+
+enum TagEnd {
+    /// The offset of the `>` ending the start tag.
+    Close(usize),
+    /// An end tag starts before the start tag is closed, so it is text rather than a tag.
+    Text,
+    /// The input ends before the start tag is closed.
+    Missing,
+}
+
+impl TagEnd {
+    fn offset_by(self, offset: usize) -> Self {
+        match self {
+            TagEnd::Close(end) => TagEnd::Close(offset + end),
+            tag_end => tag_end,
+        }
+    }
+}
+
+#[derive(Default)]
+struct UnclosedQuotes {
+    double: bool,
+    single: bool,
+}
+
+/// Find the `>` ending a start tag, given the input after its name.
+///
+/// Quotes following `=` delimit attribute values. If one is not closed, the tag ends at the first
+/// `>` as in `<script lang="ts>`, and since the quote is not closed anywhere after, it is ignored
+/// from then on. Outside of attribute values, the tag never extends past a `</`.
+fn find_tag_end(input: &[u8], unclosed_quotes: &mut UnclosedQuotes) -> TagEnd {
+    let mut index = 0;
+    let mut after_equals = false;
+
+    while let Some(&byte) = input.get(index) {
+        match byte {
+            b'>' => return TagEnd::Close(index),
+            b'<' if input.get(index + 1) == Some(&b'/') => return TagEnd::Text,
+            b'"' | b'\'' if after_equals => {
+                let unclosed = match byte {
+                    b'"' => &mut unclosed_quotes.double,
+                    _ => &mut unclosed_quotes.single,
+                };
+
+                if !*unclosed {
+                    match memchr::memchr(byte, &input[(index + 1)..]) {
+                        Some(close) => index += 1 + close,
+                        None => {
+                            *unclosed = true;
+
+                            return find_unquoted_tag_end(&input[index..]).offset_by(index);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if !byte.is_ascii_whitespace() {
+            after_equals = byte == b'=';
+        }
+
+        index += 1;
+    }
+
+    TagEnd::Missing
+}
+
+/// Find the `>` ending a start tag, ignoring quotes.
+fn find_unquoted_tag_end(input: &[u8]) -> TagEnd {
+    for index in memchr::memchr2_iter(b'>', b'<', input) {
+        if input[index] == b'>' {
+            return TagEnd::Close(index);
+        }
+
+        if input.get(index + 1) == Some(&b'/') {
+            return TagEnd::Text;
+        }
+    }
+
+    TagEnd::Missing
+}
+
+fn is_word_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || !byte.is_ascii()
+}
+
+/// Return the offset after the quoted string starting at `start`.
+fn skip_string(bytes: &[u8], start: usize) -> Option<usize> {
+    let quote = bytes[start];
+    let mut index = start + 1;
+
+    loop {
+        match *bytes.get(index)? {
+            b'\\' => index += 2,
+            b'\n' | b'\r' => return None,
+            byte if byte == quote => return Some(index + 1),
+            _ => index += 1,
+        }
+    }
+}
+
+/// Return the offset after the end of the template literal or the start of the next `${`
+/// expression, which is pushed onto `expressions` along with the current brace `depth`.
+fn skip_template(
+    bytes: &[u8],
+    mut index: usize,
+    expressions: &mut Vec<usize>,
+    depth: usize,
+) -> Option<usize> {
+    loop {
+        match *bytes.get(index)? {
+            b'\\' => index += 2,
+            b'`' => return Some(index + 1),
+            b'$' if bytes.get(index + 1) == Some(&b'{') => {
+                expressions.push(depth);
+                return Some(index + 2);
+            }
+            _ => index += 1,
+        }
+    }
+}
+
+/// Return the offset after the regular expression literal starting at `start`, not including its
+/// flags.
+fn skip_regex(bytes: &[u8], start: usize) -> Option<usize> {
+    let mut index = start + 1;
+    let mut in_class = false;
+
+    loop {
+        match *bytes.get(index)? {
+            b'\\' => index += 2,
+            b'\n' | b'\r' => return None,
+            b'[' => {
+                in_class = true;
+                index += 1;
+            }
+            b']' => {
+                in_class = false;
+                index += 1;
+            }
+            b'/' if !in_class => return Some(index + 1),
+            _ => index += 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{find_end_tag, find_script_end, find_style_end, Mismatch};
+
+    #[test]
+    fn test_find_script_end() {
+        let cases = [
+            "let a = 1;\n",
+            "const s = \"</script>\";\n",
+            "const s = '</script>';\n",
+            "const s = `</script>`;\n",
+            "const s = `${`</script>`}${{ a: '</script>' }.a}`;\n",
+            "// </script>\nlet a;\n",
+            "// </script>\rlet a;\r",
+            "/* </script> */\nlet a;\n",
+            "const r = /<\\/script>[/]/g;\n",
+            "if (a) return /[\"'`]/.test(b);\n",
+            "const a = b / 2, c = d / 3;\n",
+            "<!-- </a> -->",
+        ];
+
+        for content in cases {
+            let input = format!("{content}</script>\n");
+
+            assert_eq!(find_script_end(&input), Some(content.len()), "{content:?}");
+        }
+
+        assert_eq!(find_script_end("let a = 'b\n</script>"), None);
+        assert_eq!(find_script_end("let a;\n"), None);
+    }
+
+    #[test]
+    fn test_find_style_end() {
+        let cases = [
+            "a {}\n",
+            "a::after { content: \"</style>\"; }\n",
+            "a::after { content: '</style>'; }\n",
+            "/* </style> */\na {}\n",
+            "a { background: url(//example.com/a.png) }",
+        ];
+
+        for content in cases {
+            let input = format!("{content}</style>\n");
+
+            assert_eq!(
+                find_style_end(&input, false),
+                Some(content.len()),
+                "{content:?}"
+            );
+        }
+
+        let content = "// don't\na::after { content: '</style>'; }\n";
+        let input = format!("{content}</style>\n");
+
+        assert_eq!(find_style_end(&input, true), Some(content.len()));
+        assert_eq!(find_style_end(&input, false), None);
+
+        assert_eq!(find_style_end("/* a {}\n</style>", false), None);
+    }
+
+    #[test]
+    #[cfg(feature = "plugin")]
+    fn test_literals() {
+        use super::{script_literals, style_literals};
+
+        let input = "let a = 'b', c = `d${e + `f`}g\n`;\n// 'h'\n</script>";
+
+        assert_eq!(
+            script_literals(input)
+                .into_iter()
+                .map(|literal| &input[literal])
+                .collect::<Vec<_>>(),
+            ["'b'", "`d${", "`f`", "}g\n`"]
+        );
+        assert_eq!(script_literals("let a = `b\n"), []);
+
+        let input = "// 'a'\nb::after { content: \"c\" }";
+
+        assert_eq!(style_literals(input, true), [27..30]);
+        assert_eq!(style_literals(input, false), [3..6, 27..30]);
+    }
+
+    #[test]
+    fn test_find_end_tag() {
+        let cases = [
+            "<div></div>\n",
+            "<template></template>",
+            "<template #header />",
+            "<template v-if=\"a\n  </template>\"></template>",
+            "<div :title=\"'a > b'\"></div>",
+            "<!-- </template> --><![CDATA[ <template> ]]>",
+            "{{ a < b }} {{ c > d }}",
+            "<div title=\"a></div>",
+            "{{ a<b }}",
+            "{{ a<b }}<div></div>",
+            "<div title=\"a {{ b<c }}",
+        ];
+
+        for content in cases {
+            let input = format!("{content}</template>\n");
+
+            assert_eq!(
+                find_end_tag(&input, "template", 8),
+                Ok(content.len()),
+                "{content:?}"
+            );
+        }
+
+        assert_eq!(
+            find_end_tag("<template></template>", "template", 8),
+            Err(Mismatch::Unexpected)
+        );
+        assert_eq!(
+            find_end_tag("<div title=\"a", "template", 8),
+            Err(Mismatch::Unexpected)
+        );
+        assert_eq!(
+            find_end_tag("{{ a<b }}", "template", 8),
+            Err(Mismatch::Unexpected)
+        );
+    }
+
+    #[test]
+    fn test_find_end_tag_max_nesting_depth() {
+        let input = "<div><div></div></div></div>";
+
+        assert_eq!(find_end_tag(input, "div", 2), Ok(22));
+        assert_eq!(find_end_tag(input, "div", 1), Err(Mismatch::TooDeep));
+        assert_eq!(find_end_tag(input, "div", 0), Err(Mismatch::TooDeep));
+
+        let input = "<template>".repeat(usize::from(u16::MAX) + 1);
+
+        assert_eq!(
+            find_end_tag(&input, "template", u16::MAX),
+            Err(Mismatch::TooDeep)
+        );
+    }
+}
@@ -1,43 +1,69 @@
-use nom::{
-    branch::alt,
-    combinator::{peek, recognize, rest},
-    multi::many_till,
-    IResult, Parser,
+use super::{
+    block::parse_block,
+    util::{offset_of, parse_comment, IResult, Mismatch},
+    Block, ParseOptions,
 };
 
-use super::{block::parse_block, util::take_until_next, Block};
-
 /// Represent the sections of a Vue SFC.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Section<'a> {
     /// Represent any data before, after or between blocks.
     Raw(&'a str),
     /// See [`Block`].
+    #[cfg_attr(feature = "serde", serde(borrow))]
     Block(Block<'a>),
 }
 
-pub fn parse_section(input: &str) -> IResult<&str, Section> {
-    alt((
-        parse_block.map(Section::Block),
-        alt((
-            recognize(many_till(take_until_next("<"), peek(parse_block))),
-            rest,
-        ))
-        .map(Section::Raw),
-    ))(input)
+pub fn parse_section<'a>(input: &'a str, options: &ParseOptions) -> IResult<&'a str, Section<'a>> {
+    match parse_block(input, options) {
+        Ok((rest, block)) => return Ok((rest, Section::Block(block))),
+        Err(Mismatch::TooDeep) => return Err(Mismatch::TooDeep),
+        Err(Mismatch::Unexpected) => {}
+    }
+
+    let mut index = 0;
+
+    // The raw section ends where a block starts. Comments are skipped so commented out blocks are
+    // kept as is.
+    loop {
+        let rest = &input[index..];
+
+        index += match parse_comment(rest) {
+            Ok((after, _)) => offset_of(rest, after),
+            Err(_) => {
+                let skip = usize::from(rest.starts_with('<'));
+
+                match memchr::memchr(b'<', &rest.as_bytes()[skip..]) {
+                    Some(next) => skip + next,
+                    None => return Ok(("", Section::Raw(input))),
+                }
+            }
+        };
+
+        // A block nested too deeply fails when parsed as the next section, at its own offset.
+        if !matches!(
+            parse_block(&input[index..], options),
+            Err(Mismatch::Unexpected)
+        ) {
+            return Ok((&input[index..], Section::Raw(&input[..index])));
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use crate::parser::block::{Block, StartTag};
+    use crate::parser::span::Span;
 
-    use super::{parse_section, Section};
+    use super::{parse_section, ParseOptions, Section};
 
     #[test]
     fn test_parse_section() {
         assert_eq!(
             parse_section(
-                "<!-- A comment -->\n<script>\nlet value = true;\nconsole.log(value);\n</script>"
+                "<!-- A comment -->\n<script>\nlet value = true;\nconsole.log(value);\n</script>",
+                &ParseOptions::default()
             ),
             Ok((
                 "<script>\nlet value = true;\nconsole.log(value);\n</script>",
@@ -46,24 +72,43 @@ mod test {
         );
 
         assert_eq!(
-            parse_section("<script>\nlet value = true;\nconsole.log(value);\n</script>"),
+            parse_section(
+                "<script>\nlet value = true;\nconsole.log(value);\n</script>",
+                &ParseOptions::default()
+            ),
             Ok((
                 "",
                 Section::Block(Block {
                     start_tag: StartTag {
                         name: "script",
-                        lang: None
+                        lang: None,
+                        src: None,
+                        self_closing: false,
+                        attributes: vec![],
+                        span: Span::new(0, 8)
                     },
                     raw_start_tag: "<script>",
                     raw_end_tag: "</script>",
-                    content: "let value = true;\nconsole.log(value);\n"
+                    content: "let value = true;\nconsole.log(value);\n",
+                    span: Span::new(0, 56)
                 })
             ))
         );
 
         assert_eq!(
-            parse_section("<!-- A comment -->"),
+            parse_section("<!-- A comment -->", &ParseOptions::default()),
             Ok(("", Section::Raw("<!-- A comment -->")))
         );
+
+        assert_eq!(
+            parse_section(
+                "<!-- <style>\na {}\n</style> -->\n<script></script>",
+                &ParseOptions::default()
+            ),
+            Ok((
+                "<script></script>",
+                Section::Raw("<!-- <style>\na {}\n</style> -->\n")
+            ))
+        );
     }
 }
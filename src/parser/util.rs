@@ -1,14 +1,116 @@
-use nom::{
-    bytes::complete::{tag, take_until},
-    combinator::opt,
-    sequence::preceded,
-    IResult,
-};
+/// The input does not match what a parser expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mismatch {
+    /// The input is not of the expected form.
+    Unexpected,
+    /// Blocks of the same name are nested deeper than [`super::ParseOptions::max_nesting_depth`].
+    TooDeep,
+}
+
+/// The remaining input along with the parsed value.
+pub type IResult<I, O> = Result<(I, O), Mismatch>;
+
+/// Return the input up to the first occurrence of `byte`, which must be ASCII.
+pub fn take_until_byte(input: &str, byte: u8) -> IResult<&str, &str> {
+    match memchr::memchr(byte, input.as_bytes()) {
+        Some(index) => Ok((&input[index..], &input[..index])),
+        None => Err(Mismatch::Unexpected),
+    }
+}
+
+/// Return the longest non-empty prefix of `input` whose chars satisfy `predicate`.
+pub fn take_while1(input: &str, predicate: impl Fn(char) -> bool) -> IResult<&str, &str> {
+    match input.find(|char| !predicate(char)).unwrap_or(input.len()) {
+        0 => Err(Mismatch::Unexpected),
+        index => Ok((&input[index..], &input[..index])),
+    }
+}
+
+pub fn trim_whitespace(input: &str) -> &str {
+    input.trim_start_matches(|char: char| char.is_ascii_whitespace())
+}
+
+/// Return the input up to the first `>` outside of quoted attribute values, or up to the first `>`
+/// if a quote is not closed, such as in `<script lang="ts>`.
+pub fn take_until_tag_end(input: &str) -> IResult<&str, &str> {
+    let bytes = input.as_bytes();
+    let mut quote = None;
+    let mut after_equals = false;
+
+    for (index, &byte) in bytes.iter().enumerate() {
+        match quote {
+            Some(quote_byte) if byte == quote_byte => quote = None,
+            Some(_) => {}
+            None if byte == b'>' => return Ok((&input[index..], &input[..index])),
+            None if after_equals && (byte == b'"' || byte == b'\'') => quote = Some(byte),
+            None => {}
+        }
+
+        if !byte.is_ascii_whitespace() {
+            after_equals = byte == b'=';
+        }
+    }
+
+    take_until_byte(input, b'>')
+}
+
+/// Parse an HTML comment such as `<!-- eslint-disable -->`.
+pub fn parse_comment(input: &str) -> IResult<&str, &str> {
+    let end = input
+        .strip_prefix("<!--")
+        .and_then(|comment| comment.find("-->"))
+        .ok_or(Mismatch::Unexpected)?
+        + "<!---->".len();
+
+    Ok((&input[end..], &input[..end]))
+}
+
+/// Parse a doctype or a processing instruction such as `<!DOCTYPE html>` or `<?xml version="1.0"?>`.
+pub fn parse_declaration(input: &str) -> IResult<&str, &str> {
+    if !input.starts_with("<!") && !input.starts_with("<?") {
+        return Err(Mismatch::Unexpected);
+    }
+
+    let (rest, _) = take_until_byte(input, b'>')?;
+    let end = offset_of(input, rest) + 1;
+
+    Ok((&input[end..], &input[..end]))
+}
+
+/// Iterate over the offsets following each `\r\n`, `\n` or `\r` line break of `text`.
+pub fn line_break_ends(text: &str) -> impl Iterator<Item = usize> + '_ {
+    let bytes = text.as_bytes();
+
+    memchr::memchr2_iter(b'\n', b'\r', bytes).filter_map(move |index| {
+        match (bytes[index], bytes.get(index + 1)) {
+            (b'\r', Some(b'\n')) => None,
+            _ => Some(index + 1),
+        }
+    })
+}
+
+/// Return the first line break of `text`, `\n` if it has none.
+pub fn detect_line_break(text: &str) -> &'static str {
+    let bytes = text.as_bytes();
+
+    match memchr::memchr2(b'\n', b'\r', bytes) {
+        Some(index) if bytes[index] == b'\r' => match bytes.get(index + 1) {
+            Some(b'\n') => "\r\n",
+            _ => "\r",
+        },
+        _ => "\n",
+    }
+}
 
-pub fn take_until_next<'a>(pat: &'a str) -> impl FnMut(&'a str) -> IResult<&'a str, &'a str> {
-    preceded(opt(tag(pat)), take_until(pat))
+/// Strip one `\r\n`, `\n` or `\r` line break from the start of `input`.
+pub fn strip_line_break(input: &str) -> &str {
+    input
+        .strip_prefix("\r\n")
+        .or_else(|| input.strip_prefix(['\n', '\r']))
+        .unwrap_or(input)
 }
 
-pub fn is_ascii_whitespace(char: char) -> bool {
-    char.is_ascii_whitespace()
+/// Return the byte offset of `slice` in `source`, `slice` must be borrowed from `source`.
+pub fn offset_of(source: &str, slice: &str) -> usize {
+    slice.as_ptr() as usize - source.as_ptr() as usize
 }
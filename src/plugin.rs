@@ -8,11 +8,17 @@ use dprint_core::plugins::PluginHandler;
 use dprint_core::plugins::PluginInfo;
 
 use crate::configuration::Configuration;
+use crate::format::{format, format_block_content, format_file, is_snippet};
 
+/// GitHub repository the plugin is published from.
+const REPOSITORY: &str = "dsherret/dprint-plugin-vue";
+
+/// Formatted blocks are not kept between calls, since the output of the plugins formatting them
+/// depends on their configuration, which may change without this plugin being told.
+#[derive(Default)]
 pub struct VuePluginHandler;
 
 impl VuePluginHandler {
-    #[allow(dead_code)]
     pub const fn new() -> Self {
         VuePluginHandler
     }
@@ -26,8 +32,11 @@ impl PluginHandler<Configuration> for VuePluginHandler {
             config_key: String::from("vue"),
             file_extensions: vec![String::from("vue")],
             file_names: vec![],
-            help_url: String::new(),
-            config_schema_url: String::new(),
+            help_url: format!("https://github.com/{REPOSITORY}"),
+            config_schema_url: format!(
+                "https://plugins.dprint.dev/{REPOSITORY}/v{}/schema.json",
+                env!("CARGO_PKG_VERSION")
+            ),
         }
     }
 
@@ -48,8 +57,110 @@ impl PluginHandler<Configuration> for VuePluginHandler {
         file_path: &Path,
         file_text: &str,
         config: &Configuration,
-        format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
+        mut format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
     ) -> Result<String> {
-        crate::format::format(file_path, file_text, config, format_with_host)
+        if is_snippet(file_path) {
+            let (text, _) = format_file(file_text, config, true, |block, _| {
+                format_block_content(block, config, &mut format_with_host)
+            })?;
+
+            return Ok(text);
+        }
+
+        format(file_path, file_text, config, format_with_host)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use dprint_core::configuration::ConfigKeyMap;
+    use dprint_core::plugins::PluginHandler;
+
+    use crate::configuration::Configuration;
+
+    use super::VuePluginHandler;
+
+    #[test]
+    fn test_snippet() {
+        let config = Configuration {
+            final_newline: true,
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n<div>\n</template>\n<script>\nlet a;\n</script>";
+
+        let format_with_host = |path: &Path, raw: String, _: &ConfigKeyMap| {
+            if path.extension().unwrap() == "html" {
+                anyhow::bail!("unclosed element")
+            }
+
+            Ok(raw)
+        };
+
+        let mut handler = VuePluginHandler::new();
+
+        assert_eq!(
+            handler
+                .format_text(Path::new("file.vue"), raw, &config, format_with_host)
+                .unwrap(),
+            raw
+        );
+
+        assert!(handler
+            .format_text(
+                Path::new("/project/file.vue"),
+                raw,
+                &config,
+                format_with_host
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_format_text() {
+        let config = Configuration::default();
+        let raw = "<script>\na\n</script>\n";
+
+        let mut handler = VuePluginHandler::new();
+
+        handler
+            .format_text(Path::new("/project/file.vue"), raw, &config, |_, raw, _| {
+                Ok(raw)
+            })
+            .unwrap();
+
+        // The configuration of the plugin formatting the block may have changed since.
+        assert_eq!(
+            handler
+                .format_text(Path::new("/project/file.vue"), raw, &config, |_, raw, _| {
+                    Ok(raw.to_uppercase())
+                })
+                .unwrap(),
+            "<script>\nA\n</script>\n"
+        );
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema: serde_json::Value =
+            serde_json::from_str(include_str!("../schema.json")).unwrap();
+        let config = serde_json::to_value(Configuration::default()).unwrap();
+
+        for key in config.as_object().unwrap().keys() {
+            assert!(
+                schema["properties"].get(key).is_some(),
+                "`{key}` is missing from schema.json"
+            );
+        }
+
+        // The keys shared with the global configuration default to dprint's global defaults.
+        for key in ["indentWidth", "lineWidth", "useTabs"] {
+            assert_eq!(
+                schema["properties"][key]["default"], config[key],
+                "`{key}` has the wrong default in schema.json"
+            );
+        }
     }
 }
@@ -0,0 +1,158 @@
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+
+use anyhow::Result;
+use dprint_core::configuration::ConfigKeyMap;
+
+use crate::configuration::Configuration;
+use crate::format::{format_block_content_traced, format_file};
+use crate::parser::line_break_ends;
+use crate::report::Normalization;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RangeFormatResult {
+    /// The file content with only the blocks intersecting the ranges formatted.
+    pub text: String,
+    /// Whether formatting the whole file would change text outside of the ranges, such as a block,
+    /// the text between blocks or the order of the blocks.
+    pub out_of_range_changes: bool,
+}
+
+/// Format only the blocks intersecting the given changed lines.
+///
+/// Ranges are inclusive and 1-based, as reported by diff tools and CI systems. The text outside of
+/// these blocks is kept as is, so are the blocks merged by `mergeDuplicateStyles` since merging them
+/// changes other lines.
+pub fn format_ranges(
+    _path: &Path,
+    content: &str,
+    config: &Configuration,
+    ranges: &[RangeInclusive<usize>],
+    mut format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
+) -> Result<RangeFormatResult> {
+    let (pretty, blocks) = format_file(content, config, false, |block, trace| {
+        format_block_content_traced(block, config, &mut format_with_host, trace)
+    })?;
+
+    let mut buffer = String::with_capacity(content.len());
+    let mut offset = 0;
+
+    for block in blocks {
+        let Range { start, end } = block.input;
+        let lines = line_of(content, start)..=line_of(content, end);
+
+        let touched = ranges
+            .iter()
+            .any(|range| range.start() <= lines.end() && lines.start() <= range.end());
+
+        if touched && !block.normalizations.contains(&Normalization::Merged) {
+            buffer.push_str(&content[offset..start]);
+            buffer.push_str(&pretty[block.output]);
+            offset = end;
+        }
+    }
+
+    buffer.push_str(&content[offset..]);
+
+    Ok(RangeFormatResult {
+        // Anything `format` would change which is not in the text is outside of the ranges.
+        out_of_range_changes: buffer != pretty,
+        text: buffer,
+    })
+}
+
+/// Return the 1-based line number of the byte at `offset`.
+fn line_of(content: &str, offset: usize) -> usize {
+    line_break_ends(&content[..offset]).count() + 1
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::configuration::Configuration;
+
+    use super::format_ranges;
+
+    #[test]
+    fn test_format_ranges() {
+        let config = Configuration {
+            indent_template: true,
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw = "<template><div></div></template>\n\n<script>\nlet a;\n</script>\n";
+
+        let result = format_ranges(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            &[1..=1],
+            |_, raw, _| Ok(raw),
+        )
+        .unwrap();
+
+        assert_eq!(
+            result.text,
+            "<template>\n  <div></div>\n</template>\n\n<script>\nlet a;\n</script>\n"
+        );
+        assert!(!result.out_of_range_changes);
+
+        let result = format_ranges(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            &[4..=4],
+            |_, raw, _| Ok(raw),
+        )
+        .unwrap();
+
+        assert_eq!(result.text, raw);
+        assert!(result.out_of_range_changes);
+    }
+
+    #[test]
+    fn test_format_ranges_file_normalizations() {
+        let config = Configuration {
+            indent_template: false,
+            blank_lines_between_blocks: Some(1),
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n<div></div>\n</template>\n<script>\nlet a;  \n</script>\n";
+
+        let result = format_ranges(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            &[5..=5],
+            |_, raw, _| Ok(raw),
+        )
+        .unwrap();
+
+        // The blank line between the blocks is outside of the ranges.
+        assert_eq!(
+            result.text,
+            "<template>\n<div></div>\n</template>\n<script>\nlet a;\n</script>\n"
+        );
+        assert!(result.out_of_range_changes);
+
+        let config = Configuration {
+            blank_lines_between_blocks: None,
+            ..config
+        };
+
+        let result = format_ranges(
+            Path::new("file.vue"),
+            raw,
+            &config,
+            &[5..=5],
+            |_, raw, _| Ok(raw),
+        )
+        .unwrap();
+
+        assert!(!result.out_of_range_changes);
+    }
+}
@@ -0,0 +1,254 @@
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use dprint_core::configuration::ConfigKeyMap;
+use serde::Serialize;
+
+use crate::configuration::Configuration;
+use crate::format::{format_block_content_traced, format_file};
+use crate::validate::Diagnostic;
+
+/// Machine-readable summary of the formatting of a file.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileReport {
+    pub path: PathBuf,
+    pub blocks: Vec<BlockReport>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockReport {
+    /// The block tag name such as `template`, `script`, `style`, etc.
+    pub name: String,
+    /// The lang the block content was formatted as.
+    pub lang: Option<String>,
+    /// Whether formatting changed the block.
+    pub changed: bool,
+    /// Why the block content was not formatted, if it was not.
+    pub skipped: Option<SkipReason>,
+    /// The extensions the content was formatted as, in order, see `{block}.pipeline`.
+    pub formatters: Vec<String>,
+    /// Byte range of the block in the input.
+    pub input: Range<usize>,
+    /// Byte range of the block in the output, that of the block it was merged into if merged.
+    pub output: Range<usize>,
+    /// The normalizations applied by this plugin after formatting.
+    pub normalizations: Vec<Normalization>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Normalization {
+    /// The indentation shared by all lines was removed before formatting.
+    Dedented,
+    /// Blank lines above `template.maxBlankLines` were removed.
+    CollapsedBlankLines,
+    /// The children of the root element were dedented, see `templateBodyIndent`.
+    DedentedRootChildren,
+    /// The content was re-indented, see `indentTemplate` and `indentScriptAndStyle`.
+    Reindented,
+    /// Leading and trailing blank lines were removed, see `trimBlockContent`.
+    TrimmedBlankLines,
+    /// The block was moved, see `blockOrder` and `scriptSetupPosition`.
+    Reordered,
+    /// The block was merged with other `<style>` blocks, see `mergeDuplicateStyles`.
+    Merged,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SkipReason {
+    /// The block has no `lang` attribute and no default lang.
+    UnknownLang,
+    /// The block content is a single line longer than `minifiedThreshold`.
+    Minified,
+    /// The start tag is malformed and `compatVersion` is `0.3`.
+    MalformedStartTag,
+    /// The block is self-closing and has no content.
+    SelfClosing,
+    /// The block has a `src` attribute and no content.
+    External,
+}
+
+impl FileReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("report serialization should not fail")
+    }
+
+    /// Blocks whose content was left as is because it looks minified, see `minifiedThreshold`.
+    ///
+    /// dprint has no channel for a plugin to warn about a file it formatted, this is meant for
+    /// tools built on [`format_with_report`].
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.blocks
+            .iter()
+            .filter(|block| block.skipped == Some(SkipReason::Minified))
+            .map(|block| Diagnostic {
+                message: format!(
+                    "The content of the `<{}>` block is a single line longer than `minifiedThreshold`, it was left as is.",
+                    block.name
+                ),
+                range: block.input.clone(),
+            })
+            .collect()
+    }
+}
+
+/// What [`crate::format::format_file`] records while formatting the content of a block, see
+/// [`BlockReport`].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BlockTrace {
+    pub(crate) formatters: Vec<String>,
+    pub(crate) normalizations: Vec<Normalization>,
+}
+
+/// Format a file, returning the formatted text alongside a [`FileReport`] recording what was done
+/// to each block.
+pub fn format_with_report(
+    path: &Path,
+    content: &str,
+    config: &Configuration,
+    mut format_with_host: impl FnMut(&Path, String, &ConfigKeyMap) -> Result<String>,
+) -> Result<(String, FileReport)> {
+    let (pretty, blocks) = format_file(content, config, false, |block, trace| {
+        format_block_content_traced(block, config, &mut format_with_host, trace)
+    })?;
+
+    Ok((
+        pretty,
+        FileReport {
+            path: path.to_owned(),
+            blocks,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::Path;
+
+    use crate::configuration::{Configuration, NewLineKind};
+
+    use super::{format_with_report, Normalization};
+
+    #[test]
+    fn test_format_with_report() {
+        let config = Configuration {
+            indent_template: true,
+            use_tabs: false,
+            indent_width: 2,
+            ..Configuration::default()
+        };
+
+        let raw = "<template><div></div></template>\n<i18n>\n{}\n</i18n>\n";
+
+        let (pretty, report) =
+            format_with_report(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(
+            pretty,
+            "<template>\n  <div></div>\n</template>\n<i18n>\n{}\n</i18n>\n"
+        );
+
+        assert_eq!(
+            report.to_json(),
+            concat!(
+                r#"{"path":"file.vue","blocks":["#,
+                r#"{"name":"template","lang":"html","changed":true,"skipped":null,"formatters":["html"],"#,
+                r#""input":{"start":0,"end":32},"output":{"start":0,"end":36},"normalizations":["reindented"]},"#,
+                r#"{"name":"i18n","lang":null,"changed":false,"skipped":"unknownLang","formatters":[],"#,
+                r#""input":{"start":33,"end":50},"output":{"start":37,"end":54},"normalizations":[]}"#,
+                r#"]}"#,
+            )
+        );
+    }
+
+    #[test]
+    fn test_format_with_report_file_normalizations() {
+        let config = Configuration {
+            indent_template: false,
+            block_order: vec![String::from("template"), String::from("style")],
+            new_line_kind: Some(NewLineKind::Lf),
+            keep_byte_order_mark: true,
+            ..Configuration::default()
+        };
+
+        let raw =
+            "\u{feff}<style>\r\na {}\r\n</style>\r\n<template>\r\n<div></div>\r\n</template>\r\n";
+
+        let (pretty, report) =
+            format_with_report(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(
+            pretty,
+            "\u{feff}<template>\n<div></div>\n</template>\n<style>\na {}\n</style>\n"
+        );
+
+        let [style, template] = &report.blocks[..] else {
+            panic!("expected two blocks");
+        };
+
+        assert_eq!(&raw[style.input.clone()], "<style>\r\na {}\r\n</style>");
+        assert_eq!(&pretty[style.output.clone()], "<style>\na {}\n</style>");
+        assert_eq!(
+            &pretty[template.output.clone()],
+            "<template>\n<div></div>\n</template>"
+        );
+        assert!(style.changed && template.changed);
+        assert_eq!(style.normalizations, [Normalization::Reordered]);
+        assert_eq!(template.normalizations, [Normalization::Reordered]);
+    }
+
+    #[test]
+    fn test_format_with_report_merged() {
+        let config = Configuration {
+            merge_duplicate_styles: true,
+            style_pipeline: vec![String::from("{lang}"), String::from("prettier")],
+            ..Configuration::default()
+        };
+
+        let raw = "<style>\n.a {}\n</style>\n<style>\n.b {}\n</style>\n";
+
+        let (pretty, report) =
+            format_with_report(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        assert_eq!(pretty, "<style>\n.a {}\n.b {}\n</style>\n");
+
+        let [a, b] = &report.blocks[..] else {
+            panic!("expected two blocks");
+        };
+
+        assert_eq!(a.formatters, ["css", "prettier"]);
+        assert_eq!(a.normalizations, [Normalization::Merged]);
+        assert_eq!(b.normalizations, [Normalization::Merged]);
+        assert_eq!(a.output, b.output);
+        assert!(a.changed && b.changed);
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let config = Configuration {
+            minified_threshold: 16,
+            ..Configuration::default()
+        };
+
+        let raw = "<template>\n<div></div>\n</template>\n<style>\n.a{color:red}.b{color:blue}\n</style>\n";
+
+        let (_, report) =
+            format_with_report(Path::new("file.vue"), raw, &config, |_, raw, _| Ok(raw)).unwrap();
+
+        let diagnostics = report.diagnostics();
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            diagnostics[0].message,
+            "The content of the `<style>` block is a single line longer than `minifiedThreshold`, it was left as is."
+        );
+        assert_eq!(
+            &raw[diagnostics[0].range.clone()],
+            "<style>\n.a{color:red}.b{color:blue}\n</style>"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use crate::parser::{line_break_ends, Span};
+
+/// Text before, after or between the blocks of a Vue SFC, such as whitespace and comments.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Trivia<'a> {
+    pub text: &'a str,
+    /// Byte offsets of the text in the source file.
+    pub span: Span,
+}
+
+impl<'a> Trivia<'a> {
+    /// Whether the text is only whitespace.
+    pub fn is_whitespace(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+
+    /// Number of `\r\n`, `\n` or `\r` line breaks in the text.
+    pub fn line_breaks(&self) -> usize {
+        line_break_ends(self.text).count()
+    }
+
+    /// Iterate over the HTML comments of the text, including their delimiters. An unterminated
+    /// comment extends to the end of the text.
+    pub fn comments(&self) -> impl Iterator<Item = &'a str> {
+        let mut rest = self.text;
+
+        std::iter::from_fn(move || {
+            let start = rest.find("<!--")?;
+            let end = rest[(start + 4)..]
+                .find("-->")
+                .map_or(rest.len(), |end| start + 4 + end + 3);
+
+            let comment = &rest[start..end];
+            rest = &rest[end..];
+
+            Some(comment)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::parser::Span;
+
+    use super::Trivia;
+
+    #[test]
+    fn test_trivia() {
+        let trivia = Trivia {
+            text: "\n<!-- a -->\n\n<!-- <b> -->\n<!-- c",
+            span: Span::new(0, 33),
+        };
+
+        assert!(!trivia.is_whitespace());
+        assert_eq!(trivia.line_breaks(), 4);
+        assert_eq!(
+            trivia.comments().collect::<Vec<_>>(),
+            ["<!-- a -->", "<!-- <b> -->", "<!-- c"]
+        );
+
+        let trivia = Trivia {
+            text: "\n \n",
+            span: Span::new(0, 3),
+        };
+
+        assert!(trivia.is_whitespace());
+        assert_eq!(trivia.comments().count(), 0);
+    }
+}
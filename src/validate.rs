@@ -0,0 +1,294 @@
+use std::ops::Range;
+
+use crate::parser::{
+    offset_of, parse_declaration, parse_end_tag, parse_sections, parse_start_tag,
+    parse_well_formed_start_tag, Section,
+};
+
+/// A structural problem found in a Vue SFC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Diagnostic {
+    pub message: String,
+    /// Byte range of the problematic content.
+    pub range: Range<usize>,
+}
+
+/// Check the structure of a Vue SFC without formatting it.
+///
+/// This reports unterminated blocks, malformed start tags, duplicate attributes, duplicate
+/// `<template>`, `<script>` and `<script setup>` blocks, unmatched end tags and content that is
+/// neither a block nor a comment.
+pub fn validate(content: &str) -> Vec<Diagnostic> {
+    match parse_sections(content) {
+        Ok(sections) => validate_sections(content, &sections),
+        Err(err) => vec![Diagnostic {
+            message: err.to_string(),
+            range: 0..content.len(),
+        }],
+    }
+}
+
+/// See [`validate`], `sections` must have been parsed from `content`.
+pub(crate) fn validate_sections(content: &str, sections: &[Section]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut has_template = false;
+    let mut has_script = false;
+    let mut has_script_setup = false;
+
+    for section in sections {
+        match section {
+            Section::Raw(text) => validate_raw(content, text, &mut diagnostics),
+            Section::Block(block) => {
+                if parse_well_formed_start_tag(block.raw_start_tag).is_err() {
+                    let start = offset_of(content, block.raw_start_tag);
+
+                    diagnostics.push(Diagnostic {
+                        message: format!(
+                            "Malformed `<{}>` start tag, its attributes were ignored.",
+                            block.start_tag.name
+                        ),
+                        range: start..(start + block.raw_start_tag.len()),
+                    });
+                }
+
+                let attributes = &block.start_tag.attributes;
+
+                for (index, attribute) in attributes.iter().enumerate() {
+                    if attributes[..index]
+                        .iter()
+                        .any(|previous| previous.name.eq_ignore_ascii_case(attribute.name))
+                    {
+                        diagnostics.push(Diagnostic {
+                            message: format!(
+                                "Duplicate `{}` attribute, the first one is used.",
+                                attribute.name
+                            ),
+                            range: Range::from(attribute.span),
+                        });
+                    }
+                }
+
+                if block.start_tag.name.eq_ignore_ascii_case("template") {
+                    if has_template {
+                        diagnostics.push(Diagnostic {
+                            message: String::from("Duplicate `<template>` block."),
+                            range: block.range(content),
+                        });
+                    }
+
+                    has_template = true;
+                }
+
+                if block.start_tag.name.eq_ignore_ascii_case("script") {
+                    let (has_same_script, message) = if block.start_tag.setup() {
+                        (&mut has_script_setup, "Duplicate `<script setup>` block.")
+                    } else {
+                        (&mut has_script, "Duplicate `<script>` block.")
+                    };
+
+                    if *has_same_script {
+                        diagnostics.push(Diagnostic {
+                            message: String::from(message),
+                            range: block.range(content),
+                        });
+                    }
+
+                    *has_same_script = true;
+                }
+            }
+        }
+    }
+
+    diagnostics
+}
+
+/// Report anything but whitespace, comments and declarations such as `<!DOCTYPE html>` between
+/// blocks.
+fn validate_raw(source: &str, mut text: &str, diagnostics: &mut Vec<Diagnostic>) {
+    // A byte order mark is allowed at the start of the file.
+    if offset_of(source, text) == 0 {
+        text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    }
+
+    loop {
+        text = text.trim_start();
+
+        if text.is_empty() {
+            break;
+        }
+
+        if let Some(comment) = text.strip_prefix("<!--") {
+            match comment.find("-->") {
+                Some(end) => text = &comment[(end + 3)..],
+                None => {
+                    let start = offset_of(source, text);
+
+                    diagnostics.push(Diagnostic {
+                        message: String::from("Unterminated comment."),
+                        range: start..(start + text.len()),
+                    });
+
+                    break;
+                }
+            }
+
+            continue;
+        }
+
+        if let Ok((rest, _)) = parse_declaration(text) {
+            text = rest;
+
+            continue;
+        }
+
+        if let Ok((rest, name)) = parse_end_tag(text) {
+            let start = offset_of(source, text);
+
+            diagnostics.push(Diagnostic {
+                message: format!("Unmatched `</{name}>` end tag."),
+                range: start..offset_of(source, rest),
+            });
+
+            text = rest;
+
+            continue;
+        }
+
+        let (stray, rest) = text.split_at(text.find("<!--").unwrap_or(text.len()));
+        let stray = stray.trim_end();
+
+        let message = match parse_start_tag(stray) {
+            Ok((_, start_tag)) => format!("Unterminated `<{}>` block.", start_tag.name),
+            Err(_) => String::from("Unexpected content outside of a block."),
+        };
+
+        let start = offset_of(source, stray);
+
+        diagnostics.push(Diagnostic {
+            message,
+            range: start..(start + stray.len()),
+        });
+
+        text = rest;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{validate, Diagnostic};
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            validate("<!-- A comment -->\n<template></template>\n\n<script></script>\n"),
+            []
+        );
+
+        assert_eq!(
+            validate("<?xml version=\"1.0\"?>\n<!DOCTYPE html>\n<template></template>\n"),
+            []
+        );
+
+        assert_eq!(validate("\u{feff}<template></template>\n"), []);
+
+        assert_eq!(
+            validate("<template></template>\n\u{feff}"),
+            [Diagnostic {
+                message: String::from("Unexpected content outside of a block."),
+                range: 22..25,
+            }]
+        );
+
+        assert_eq!(
+            validate("<template></template>\n<template></template>\n"),
+            [Diagnostic {
+                message: String::from("Duplicate `<template>` block."),
+                range: 22..43,
+            }]
+        );
+
+        assert_eq!(
+            validate("<script></script>\nstray <!-- A comment -->\n<template>\n"),
+            [
+                Diagnostic {
+                    message: String::from("Unexpected content outside of a block."),
+                    range: 18..23,
+                },
+                Diagnostic {
+                    message: String::from("Unterminated `<template>` block."),
+                    range: 43..53,
+                }
+            ]
+        );
+
+        assert_eq!(
+            validate("<script></script>\n<!-- A comment"),
+            [Diagnostic {
+                message: String::from("Unterminated comment."),
+                range: 18..32,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_duplicate_script() {
+        assert_eq!(validate("<script></script>\n<script setup></script>\n"), []);
+
+        assert_eq!(
+            validate("<script></script>\n<script lang=\"ts\"></script>\n"),
+            [Diagnostic {
+                message: String::from("Duplicate `<script>` block."),
+                range: 18..45,
+            }]
+        );
+
+        assert_eq!(
+            validate("<script setup></script>\n<script></script>\n<SCRIPT setup></SCRIPT>\n"),
+            [Diagnostic {
+                message: String::from("Duplicate `<script setup>` block."),
+                range: 42..65,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_duplicate_attribute() {
+        assert_eq!(
+            validate("<template></template>\n<script lang=\"ts\" LANG=\"js\"></script>\n"),
+            [Diagnostic {
+                message: String::from("Duplicate `LANG` attribute, the first one is used."),
+                range: 40..49,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_malformed_start_tag() {
+        assert_eq!(
+            validate("<script lang= >\n</script>\n"),
+            [Diagnostic {
+                message: String::from(
+                    "Malformed `<script>` start tag, its attributes were ignored."
+                ),
+                range: 0..15,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_validate_unmatched_end_tag() {
+        assert_eq!(
+            validate("<template></template>\n</div>\n</span >\n<script></script>\n"),
+            [
+                Diagnostic {
+                    message: String::from("Unmatched `</div>` end tag."),
+                    range: 22..28,
+                },
+                Diagnostic {
+                    message: String::from("Unmatched `</span>` end tag."),
+                    range: 29..37,
+                }
+            ]
+        );
+    }
+}
@@ -0,0 +1,140 @@
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+
+use crate::descriptor::SfcDescriptor;
+use crate::lang::script_type_lang;
+use crate::parser::{ParseError, Span};
+
+/// The content of a block of a Vue SFC, extracted to be handled as a file of its own lang.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VirtualDocument<'a> {
+    /// Name of the block, such as `script`.
+    pub block: &'a str,
+    /// Lang of the content, from the `lang` attribute, the `type` attribute of `<script>` blocks
+    /// or the default lang of the block. `None` for custom blocks without a `lang` attribute and
+    /// for scripts of an unknown type.
+    pub lang: Option<Cow<'a, str>>,
+    /// Path of the SFC with the index of the block and the lang appended, such as
+    /// `App.vue.1.ts`, so the document is recognized by its extension.
+    pub path_hint: PathBuf,
+    pub content: &'a str,
+    /// Byte offsets of the content in the SFC.
+    pub span: Span,
+}
+
+/// Extract the content of every block of the Vue SFC at `path` as a [`VirtualDocument`], in
+/// source order.
+///
+/// Blocks without content, such as `<style src="./style.css" />`, are left out.
+pub fn virtual_documents<'a>(
+    path: &Path,
+    source: &'a str,
+) -> Result<Vec<VirtualDocument<'a>>, ParseError> {
+    let descriptor = SfcDescriptor::parse(source)?;
+
+    let documents = descriptor
+        .blocks()
+        .enumerate()
+        .filter(|(_, block)| !block.start_tag.self_closing)
+        .map(|(index, block)| {
+            let name = block.start_tag.name;
+            let lang = match (
+                block.start_tag.decoded_lang(),
+                block.start_tag.script_type(),
+            ) {
+                (Some(lang), _) => Some(lang),
+                (None, Some(script_type)) if name.eq_ignore_ascii_case("script") => {
+                    script_type_lang(&script_type).map(Cow::Borrowed)
+                }
+                (None, _) => default_lang(name).map(Cow::Borrowed),
+            };
+
+            let mut path_hint = path.as_os_str().to_owned();
+            path_hint.push(format!(
+                ".{index}.{}",
+                lang.as_deref().unwrap_or(&name.to_ascii_lowercase())
+            ));
+
+            let range = block.content_range(source);
+
+            VirtualDocument {
+                block: name,
+                lang,
+                path_hint: PathBuf::from(path_hint),
+                content: block.content,
+                span: Span::new(range.start, range.end),
+            }
+        })
+        .collect();
+
+    Ok(documents)
+}
+
+fn default_lang(block: &str) -> Option<&'static str> {
+    match block.to_ascii_lowercase().as_str() {
+        "template" => Some("html"),
+        "script" => Some("js"),
+        "style" => Some("css"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use crate::parser::Span;
+
+    use super::virtual_documents;
+
+    #[test]
+    fn test_virtual_documents() {
+        let source = concat!(
+            "<template>\n<div></div>\n</template>\n",
+            "<script setup lang=\"ts\">\nlet a = 1;\n</script>\n",
+            "<style src=\"./a.css\" />\n",
+            "<I18n>\n{}\n</I18n>\n",
+            "<script type=\"text/x-template\">\n<div></div>\n</script>\n",
+        );
+
+        let documents = virtual_documents(Path::new("src/App.vue"), source).unwrap();
+
+        assert_eq!(
+            documents
+                .iter()
+                .map(|document| (
+                    document.block,
+                    document.lang.as_deref(),
+                    document.path_hint.clone(),
+                    document.content,
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "template",
+                    Some("html"),
+                    PathBuf::from("src/App.vue.0.html"),
+                    "<div></div>\n"
+                ),
+                (
+                    "script",
+                    Some("ts"),
+                    PathBuf::from("src/App.vue.1.ts"),
+                    "let a = 1;\n"
+                ),
+                ("I18n", None, PathBuf::from("src/App.vue.3.i18n"), "{}\n"),
+                (
+                    "script",
+                    None,
+                    PathBuf::from("src/App.vue.4.script"),
+                    "<div></div>\n"
+                ),
+            ]
+        );
+
+        assert_eq!(documents[1].span, Span::new(60, 71));
+        assert_eq!(&source[60..71], documents[1].content);
+
+        assert!(virtual_documents(Path::new("App.vue"), "<script>").is_err());
+    }
+}
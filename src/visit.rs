@@ -0,0 +1,215 @@
+use std::iter;
+
+use crate::descriptor::SfcDescriptor;
+use crate::parser::{
+    find_unterminated_start_tag, offset_of, Block, ParseError, ParseErrorKind, ParseOptions,
+    Section, Sections, StartTag,
+};
+
+/// Callbacks invoked by [`visit_sfc`], all of them do nothing by default.
+pub trait SfcVisitor<'a> {
+    /// Called for every block, before its start tag is visited.
+    fn visit_block(&mut self, _block: &Block<'a>) {}
+
+    fn visit_start_tag(&mut self, _start_tag: &StartTag<'a>) {}
+
+    /// Called for the text before, after or between blocks.
+    fn visit_trivia(&mut self, _text: &'a str) {}
+}
+
+/// Walk the sections of `descriptor` in source order.
+pub fn visit_sfc<'a>(descriptor: &SfcDescriptor<'a>, visitor: &mut impl SfcVisitor<'a>) {
+    for section in &descriptor.sections {
+        match section {
+            Section::Raw(text) => visitor.visit_trivia(text),
+            Section::Block(block) => {
+                visitor.visit_block(block);
+                visitor.visit_start_tag(&block.start_tag);
+            }
+        }
+    }
+}
+
+/// Call `f` with every block of `source` in source order, without collecting the sections.
+///
+/// Fail as [`SfcDescriptor::parse`] does, after visiting the blocks preceding the error.
+pub fn visit_blocks<'a>(source: &'a str, mut f: impl FnMut(&Block<'a>)) -> Result<(), ParseError> {
+    for block in iter_blocks(source) {
+        f(&block?);
+    }
+
+    Ok(())
+}
+
+/// Iterate over the blocks of `source` in source order, parsing them one at a time, see
+/// [`visit_blocks`].
+///
+/// A block missing its end tag yields an [`ParseErrorKind::Unterminated`] error, as
+/// [`SfcDescriptor::parse`] fails. The iterator ends after yielding an error.
+pub fn iter_blocks(source: &str) -> impl Iterator<Item = Result<Block<'_>, ParseError>> {
+    let mut sections = Sections::new(source, ParseOptions::default()).peekable();
+    let mut failed = false;
+
+    iter::from_fn(move || loop {
+        if failed {
+            return None;
+        }
+
+        match sections.next()? {
+            Ok(Section::Block(block)) => return Some(Ok(block)),
+            Ok(Section::Raw(text)) => {
+                // An error in the next section takes precedence, as it does when parsing the whole
+                // file.
+                if sections.peek().is_some_and(Result::is_err) {
+                    continue;
+                }
+
+                let is_last = sections.peek().is_none();
+
+                if let Some((offset, start_tag)) = find_unterminated_start_tag(text, is_last) {
+                    failed = true;
+
+                    return Some(Err(ParseError::new(
+                        source,
+                        offset_of(source, text) + offset,
+                        Some(start_tag.name),
+                        ParseErrorKind::Unterminated,
+                    )));
+                }
+            }
+            Err(err) => {
+                failed = true;
+
+                return Some(Err(err));
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::ops::Range;
+
+    use crate::descriptor::SfcDescriptor;
+    use crate::parser::{Block, StartTag};
+
+    use super::{iter_blocks, visit_blocks, visit_sfc, SfcVisitor};
+
+    #[derive(Default)]
+    struct Recorder<'a> {
+        events: Vec<String>,
+        langs: Vec<Option<&'a str>>,
+    }
+
+    impl<'a> SfcVisitor<'a> for Recorder<'a> {
+        fn visit_block(&mut self, block: &Block<'a>) {
+            self.events.push(format!("block {}", block.start_tag.name));
+        }
+
+        fn visit_start_tag(&mut self, start_tag: &StartTag<'a>) {
+            self.langs.push(start_tag.lang);
+        }
+
+        fn visit_trivia(&mut self, text: &'a str) {
+            self.events.push(format!("trivia {text:?}"));
+        }
+    }
+
+    #[test]
+    fn test_visit_sfc() {
+        let descriptor = SfcDescriptor::parse(
+            "<!-- A comment -->\n<template></template>\n<script lang=\"ts\"></script>\n",
+        )
+        .unwrap();
+
+        let mut recorder = Recorder::default();
+
+        visit_sfc(&descriptor, &mut recorder);
+
+        assert_eq!(
+            recorder.events,
+            [
+                "trivia \"<!-- A comment -->\\n\"",
+                "block template",
+                "trivia \"\\n\"",
+                "block script",
+                "trivia \"\\n\"",
+            ]
+        );
+
+        assert_eq!(recorder.langs, [None, Some("ts")]);
+    }
+
+    #[test]
+    fn test_visit_blocks() {
+        let source = "<template></template>\n<script lang=\"ts\">\nlet a;\n</script>\n";
+
+        let mut blocks = Vec::new();
+
+        visit_blocks(source, |block| {
+            blocks.push((block.start_tag.name, &source[Range::from(block.span)]));
+        })
+        .unwrap();
+
+        assert_eq!(
+            blocks,
+            [
+                ("template", "<template></template>"),
+                ("script", "<script lang=\"ts\">\nlet a;\n</script>"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_iter_blocks() {
+        let source = "<template></template>\n<script>\nlet a;\n</script>\n<style>";
+
+        let blocks = iter_blocks(source)
+            .map(|block| block.map(|block| block.start_tag.name))
+            .collect::<Vec<_>>();
+
+        assert_eq!(blocks.len(), 3);
+        assert_eq!(blocks[..2], [Ok("template"), Ok("script")]);
+        assert_eq!(
+            blocks[2].as_ref().unwrap_err().to_string(),
+            "Unterminated `<style>` block starting at line 5, column 1."
+        );
+
+        let source = format!(
+            "<script></script>\n<template>{}</template>\n<style></style>\n",
+            "<template>".repeat(300)
+        );
+
+        let mut blocks = iter_blocks(&source);
+
+        assert!(blocks.next().unwrap().is_ok());
+        assert_eq!(
+            blocks.next().unwrap().unwrap_err().to_string(),
+            "`<template>` block starting at line 2, column 1 nests elements deeper than 256 levels."
+        );
+        assert!(blocks.next().is_none());
+    }
+
+    #[test]
+    fn test_iter_blocks_unterminated() {
+        let sources = [
+            "<template></template>\n<script>\nlet a;\n",
+            "<template>\n<div></div>\n<script></script>\n",
+            "<script></script>\n<i18n>\n{}\n",
+            "<script></script>\n<br>\n",
+        ];
+
+        for source in sources {
+            let blocks = iter_blocks(source).collect::<Result<Vec<_>, _>>();
+            let descriptor = SfcDescriptor::parse(source)
+                .map(|descriptor| descriptor.blocks().cloned().collect::<Vec<_>>());
+
+            assert_eq!(blocks, descriptor, "{source:?}");
+            assert_eq!(
+                visit_blocks(source, |_| {}),
+                descriptor.map(|_| ()),
+                "{source:?}"
+            );
+        }
+    }
+}